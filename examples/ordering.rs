@@ -0,0 +1,87 @@
+// Copyright 2019-2022 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Adds a colored view after applying vibrancy, like the webview of a Tauri window,
+//! and checks that the effect view stays behind it.
+
+#[cfg(target_os = "macos")]
+#[macro_use]
+extern crate objc;
+
+fn main() {
+    use winit::{
+        event::{Event, WindowEvent},
+        event_loop::{ControlFlow, EventLoop},
+        window::WindowBuilder,
+    };
+
+    let event_loop = EventLoop::new();
+
+    // only checked on macOS, but the window is kept open everywhere
+    let _window = WindowBuilder::new()
+        .with_transparent(true)
+        .build(&event_loop)
+        .unwrap();
+
+    #[cfg(target_os = "macos")]
+    check_ordering(&_window);
+
+    event_loop.run(move |event, _, control_flow| {
+        *control_flow = ControlFlow::Wait;
+
+        if let Event::WindowEvent {
+            event: WindowEvent::CloseRequested,
+            ..
+        } = event
+        {
+            *control_flow = ControlFlow::Exit
+        }
+    });
+}
+
+#[cfg(target_os = "macos")]
+fn check_ordering(window: &winit::window::Window) {
+    use cocoa::{
+        appkit::{NSView, NSWindow},
+        base::{id, nil, YES},
+        foundation::{NSPoint, NSRect, NSSize},
+    };
+    use window_vibrancy::*;
+    use winit::platform::macos::WindowExtMacOS;
+
+    apply_vibrancy(window, NSVisualEffectMaterial::HudWindow, None, None)
+        .expect("Unsupported platform! 'apply_vibrancy' is only supported on macOS");
+
+    unsafe {
+        let ns_window = window.ns_window() as id;
+        let content_view = ns_window.contentView();
+
+        // stands in for a webview, in front of everything like `addSubview:` does
+        let colored_view = NSView::alloc(nil).initWithFrame_(NSRect::new(
+            NSPoint::new(40.0, 40.0),
+            NSSize::new(200.0, 120.0),
+        ));
+        let _: () = msg_send![colored_view, setWantsLayer: YES];
+        let color: id = msg_send![class!(NSColor), systemRedColor];
+        let color: *mut std::ffi::c_void = msg_send![color, CGColor];
+        let layer: id = msg_send![colored_view, layer];
+        let _: () = msg_send![layer, setBackgroundColor: color];
+        let _: () = msg_send![content_view, addSubview: colored_view];
+        let _: () = msg_send![colored_view, release];
+
+        ensure_vibrancy_behind_content(window).unwrap();
+
+        let effect_view = get_vibrancy_view(window)
+            .unwrap()
+            .expect("the effect view was not inserted");
+        let subviews: id = msg_send![content_view, subviews];
+        let first: id = msg_send![subviews, firstObject];
+        assert_eq!(
+            first as *mut std::ffi::c_void,
+            effect_view.as_ptr(),
+            "the effect view is not behind the colored view"
+        );
+        println!("the effect view is behind the colored view");
+    }
+}
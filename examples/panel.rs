@@ -0,0 +1,78 @@
+// Copyright 2019-2022 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! A floating, non-activating vibrant panel like the Spotlight search field.
+
+#[cfg(target_os = "macos")]
+#[macro_use]
+extern crate objc;
+
+#[cfg(not(target_os = "macos"))]
+fn main() {
+    println!("This example only runs on macOS.");
+}
+
+#[cfg(target_os = "macos")]
+fn main() {
+    use cocoa::{
+        appkit::{
+            NSApp, NSApplication, NSApplicationActivationPolicy, NSBackingStoreType, NSWindow,
+            NSWindowStyleMask,
+        },
+        base::{id, nil, NO, YES},
+        foundation::{NSAutoreleasePool, NSPoint, NSRect, NSSize},
+    };
+    use raw_window_handle::{AppKitWindowHandle, HasRawWindowHandle, RawWindowHandle};
+    use window_vibrancy::*;
+
+    struct Panel(id);
+
+    unsafe impl HasRawWindowHandle for Panel {
+        fn raw_window_handle(&self) -> RawWindowHandle {
+            let mut handle = AppKitWindowHandle::empty();
+            handle.ns_window = self.0 as _;
+            RawWindowHandle::AppKit(handle)
+        }
+    }
+
+    // `NSWindowStyleMaskNonactivatingPanel`
+    const NON_ACTIVATING_PANEL: u64 = 1 << 7;
+
+    unsafe {
+        let _pool = NSAutoreleasePool::new(nil);
+
+        let app = NSApp();
+        app.setActivationPolicy_(
+            NSApplicationActivationPolicy::NSApplicationActivationPolicyAccessory,
+        );
+
+        let style_mask = NSWindowStyleMask::from_bits_truncate(
+            NSWindowStyleMask::NSBorderlessWindowMask.bits() | NON_ACTIVATING_PANEL,
+        );
+        let panel: id = msg_send![class!(NSPanel), alloc];
+        let panel = panel.initWithContentRect_styleMask_backing_defer_(
+            NSRect::new(NSPoint::new(0.0, 0.0), NSSize::new(640.0, 64.0)),
+            style_mask,
+            NSBackingStoreType::NSBackingStoreBuffered,
+            NO,
+        );
+        let _: () = msg_send![panel, setFloatingPanel: YES];
+        let _: () = msg_send![panel, setHidesOnDeactivate: NO];
+        panel.setMovableByWindowBackground_(YES);
+        panel.setOpaque_(NO);
+        panel.setBackgroundColor_(msg_send![class!(NSColor), clearColor]);
+        panel.center();
+
+        apply_vibrancy(
+            Panel(panel),
+            NSVisualEffectMaterial::HudWindow,
+            None,
+            Some(12.0),
+        )
+        .expect("Unsupported platform! 'apply_vibrancy' is only supported on macOS");
+
+        panel.orderFrontRegardless();
+        app.run();
+    }
+}
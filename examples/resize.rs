@@ -0,0 +1,96 @@
+// Copyright 2019-2022 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! Applies vibrancy, grows the window and checks that the effect view still fills the content view.
+
+#[cfg(target_os = "macos")]
+#[macro_use]
+extern crate objc;
+
+fn main() {
+    #[cfg(target_os = "macos")]
+    use window_vibrancy::*;
+    use winit::{
+        dpi::LogicalSize,
+        event::{Event, WindowEvent},
+        event_loop::{ControlFlow, EventLoop},
+        window::WindowBuilder,
+    };
+
+    let event_loop = EventLoop::new();
+
+    let window = WindowBuilder::new()
+        .with_inner_size(LogicalSize::new(400.0, 300.0))
+        .with_transparent(true)
+        .build(&event_loop)
+        .unwrap();
+
+    #[cfg(target_os = "macos")]
+    apply_vibrancy(&window, NSVisualEffectMaterial::HudWindow, None, None)
+        .expect("Unsupported platform! 'apply_vibrancy' is only supported on macOS");
+
+    window.set_inner_size(LogicalSize::new(800.0, 600.0));
+
+    event_loop.run(move |event, _, control_flow| {
+        *control_flow = ControlFlow::Wait;
+
+        match event {
+            Event::WindowEvent {
+                event: WindowEvent::CloseRequested,
+                ..
+            } => *control_flow = ControlFlow::Exit,
+            Event::WindowEvent {
+                event: WindowEvent::Resized(size),
+                ..
+            } if size.width > 400 => {
+                #[cfg(target_os = "macos")]
+                check_effect_view(&window);
+                *control_flow = ControlFlow::Exit;
+            }
+            _ => (),
+        }
+    });
+}
+
+#[cfg(target_os = "macos")]
+fn check_effect_view(window: &winit::window::Window) {
+    use cocoa::{
+        appkit::{NSView, NSWindow},
+        base::{id, nil, BOOL, YES},
+        foundation::{NSString, NSUInteger},
+    };
+    use winit::platform::macos::WindowExtMacOS;
+
+    unsafe {
+        let ns_window = window.ns_window() as id;
+        let content_view = ns_window.contentView();
+        let bounds = NSView::bounds(content_view);
+        let identifier = NSString::alloc(nil).init_str("app.tauri.window-vibrancy.effect-view");
+
+        let subviews: id = msg_send![content_view, subviews];
+        let count: NSUInteger = msg_send![subviews, count];
+        let effect_view = (0..count)
+            .map(|i| -> id { msg_send![subviews, objectAtIndex: i] })
+            .find(|&view| {
+                let view_identifier: id = msg_send![view, identifier];
+                if view_identifier == nil {
+                    return false;
+                }
+                let equal: BOOL = msg_send![view_identifier, isEqualToString: identifier];
+                equal == YES
+            })
+            .expect("the effect view was not inserted");
+
+        let frame = NSView::frame(effect_view);
+        assert_eq!(
+            (frame.size.width, frame.size.height),
+            (bounds.size.width, bounds.size.height),
+            "the effect view doesn't fill the content view after resizing"
+        );
+        println!(
+            "the effect view follows the window: {}x{}",
+            frame.size.width, frame.size.height
+        );
+    }
+}
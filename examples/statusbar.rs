@@ -0,0 +1,122 @@
+// Copyright 2019-2022 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+//! A menu bar app whose status item toggles a vibrant panel below it.
+
+#[cfg(target_os = "macos")]
+#[macro_use]
+extern crate objc;
+
+#[cfg(not(target_os = "macos"))]
+fn main() {
+    println!("This example only runs on macOS.");
+}
+
+#[cfg(target_os = "macos")]
+fn main() {
+    use cocoa::{
+        appkit::{
+            NSApp, NSApplication, NSApplicationActivationPolicy, NSBackingStoreType, NSWindow,
+            NSWindowStyleMask,
+        },
+        base::{id, nil, NO, YES},
+        foundation::{NSAutoreleasePool, NSPoint, NSRect, NSSize, NSString},
+    };
+    use objc::{
+        declare::ClassDecl,
+        runtime::{Object, Sel},
+    };
+    use raw_window_handle::{AppKitWindowHandle, HasRawWindowHandle, RawWindowHandle};
+    use window_vibrancy::*;
+
+    struct Panel(id);
+
+    unsafe impl HasRawWindowHandle for Panel {
+        fn raw_window_handle(&self) -> RawWindowHandle {
+            let mut handle = AppKitWindowHandle::empty();
+            handle.ns_window = self.0 as _;
+            RawWindowHandle::AppKit(handle)
+        }
+    }
+
+    // `NSWindowStyleMaskNonactivatingPanel`
+    const NON_ACTIVATING_PANEL: u64 = 1 << 7;
+    // `NSVariableStatusItemLength`
+    const VARIABLE_LENGTH: f64 = -1.0;
+
+    // shows the panel right below the status item, or hides it
+    extern "C" fn toggle(this: &Object, _: Sel, _sender: id) {
+        unsafe {
+            let panel = *this.get_ivar::<id>("panel");
+            let status_item = *this.get_ivar::<id>("statusItem");
+            let visible: bool = msg_send![panel, isVisible];
+            if visible {
+                let _: () = msg_send![panel, orderOut: nil];
+                return;
+            }
+
+            let button: id = msg_send![status_item, button];
+            let button_window: id = msg_send![button, window];
+            let button_frame = NSWindow::frame(button_window);
+            let panel_frame = NSWindow::frame(panel);
+            let origin = NSPoint::new(
+                button_frame.origin.x + (button_frame.size.width - panel_frame.size.width) / 2.0,
+                button_frame.origin.y - panel_frame.size.height - 4.0,
+            );
+            panel.setFrameOrigin_(origin);
+            panel.orderFrontRegardless();
+        }
+    }
+
+    unsafe {
+        let _pool = NSAutoreleasePool::new(nil);
+
+        let app = NSApp();
+        app.setActivationPolicy_(
+            NSApplicationActivationPolicy::NSApplicationActivationPolicyAccessory,
+        );
+
+        let style_mask = NSWindowStyleMask::from_bits_truncate(
+            NSWindowStyleMask::NSBorderlessWindowMask.bits() | NON_ACTIVATING_PANEL,
+        );
+        let panel: id = msg_send![class!(NSPanel), alloc];
+        let panel = panel.initWithContentRect_styleMask_backing_defer_(
+            NSRect::new(NSPoint::new(0.0, 0.0), NSSize::new(280.0, 360.0)),
+            style_mask,
+            NSBackingStoreType::NSBackingStoreBuffered,
+            NO,
+        );
+        let _: () = msg_send![panel, setFloatingPanel: YES];
+        let _: () = msg_send![panel, setHidesOnDeactivate: NO];
+        panel.setOpaque_(NO);
+        panel.setBackgroundColor_(msg_send![class!(NSColor), clearColor]);
+
+        // before the panel is shown for the first time
+        apply_vibrancy(
+            Panel(panel),
+            NSVisualEffectMaterial::Popover,
+            None,
+            Some(10.0),
+        )
+        .expect("Unsupported platform! 'apply_vibrancy' is only supported on macOS");
+        set_window_shadow(Panel(panel), true).unwrap();
+
+        let status_bar: id = msg_send![class!(NSStatusBar), systemStatusBar];
+        let status_item: id = msg_send![status_bar, statusItemWithLength: VARIABLE_LENGTH];
+        let button: id = msg_send![status_item, button];
+        let _: () = msg_send![button, setTitle: NSString::alloc(nil).init_str("◐")];
+
+        let mut decl = ClassDecl::new("StatusItemTarget", class!(NSObject)).unwrap();
+        decl.add_ivar::<id>("panel");
+        decl.add_ivar::<id>("statusItem");
+        decl.add_method(sel!(toggle:), toggle as extern "C" fn(&Object, Sel, id));
+        let target: id = msg_send![decl.register(), new];
+        (*target).set_ivar("panel", panel);
+        (*target).set_ivar("statusItem", status_item);
+        let _: () = msg_send![button, setTarget: target];
+        let _: () = msg_send![button, setAction: sel!(toggle:)];
+
+        app.run();
+    }
+}
@@ -7,6 +7,9 @@
 //! ## Platform-specific
 //!
 //! - **Linux**: Unsupported, Blur and any vibrancy effects are controlled by the compositor installed on the end-user system.
+//!   The exception is [`apply_blur`] on X11 with KWin, the compositor of KDE Plasma, behind the `x11` feature.
+//! - **Windows**: Applying an effect clears the effect previously applied to the same window by this crate,
+//!   so switching effects doesn't require calling the matching `clear_*` function first.
 //!
 //! # Example
 //!
@@ -23,54 +26,267 @@
 
 mod macos;
 mod windows;
+#[cfg(all(target_os = "linux", feature = "x11"))]
+mod x11;
 
-pub use macos::{NSVisualEffectMaterial, NSVisualEffectState};
+pub use macos::{
+    Appearance, NSVisualEffectBlendingMode, NSVisualEffectMaterial, NSVisualEffectState,
+    ObserverHandle, VibrancyAnchor, VibrancyFrame, VibrancyInsets, VibrancyMask, VibrancyOptions,
+    VibrancyPlacement,
+};
+pub use windows::{
+    AccentFlags, AccentPolicy, AccentState, Applied, BorderColor, CornerPreference,
+    DwmAttributeValue, EffectOptions, Margins, SystemBackdropType,
+};
 
 /// a tuple of RGBA colors. Each value has minimum of 0 and maximum of 255.
 pub type Color = (u8, u8, u8, u8);
 
-/// Applies blur effect to window. Works only on Windows 7, Windows 10 v1809 or newer and Windows 11.
+/// Applies blur effect to window. Works only on Windows 7, Windows 8, Windows 8.1, Windows 10 v1809 or newer and Windows 11.
 ///
 /// ## Argumesnts:
 ///
-/// - *`color`* is ignored on Windows 7 and has no effect.
+/// - *`color`* is ignored on Windows 7, Windows 8 and Windows 8.1 and has no effect.
+///   A fully transparent color is drawn with the minimal non-zero alpha instead.
+///
+/// Returns [`Error::DwmCompositionDisabled`] on Windows 7 when desktop composition is turned off.
+/// Returns [`Error::LayeredWindow`] for windows with the `WS_EX_LAYERED` extended style,
+/// use [`apply_blur_with_options`] with [`EffectOptions::allow_layered`] to apply it anyway.
 ///
 /// ## Platform-specific
 ///
-/// - **Linux / macOS**: Unsupported.
+/// - **Linux**: Only supported on X11 with KWin and the `x11` feature, which sets `_KDE_NET_WM_BLUR_BEHIND_REGION`
+///   on the window. *`color`* is ignored, and the window needs a visual with an alpha channel to show the blur.
+///   This is best effort: other compositors, or KWin with the blur effect turned off, ignore the property,
+///   so this succeeds without blurring the window there.
+///   The window must be on the display of `DISPLAY`, see [`apply_blur_x11`] for other displays.
+///   X errors, like for a window that was destroyed, are returned as [`Error::PlatformError`].
+/// - **macOS**: Unsupported.
 pub fn apply_blur(
     window: impl raw_window_handle::HasRawWindowHandle,
     #[allow(unused)] color: Option<Color>,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => windows::apply_blur(
+            windows::to_hwnd(handle.hwnd),
+            EffectOptions {
+                color,
+                ..Default::default()
+            },
+        )
+        .map(|_| ()),
+        #[cfg(all(target_os = "linux", feature = "x11"))]
+        raw_window_handle::RawWindowHandle::Xlib(handle) => {
+            x11::apply_blur(std::ptr::null_mut(), handle.window, &[])
+        }
+        #[cfg(all(target_os = "linux", feature = "x11"))]
+        raw_window_handle::RawWindowHandle::Xcb(handle) => {
+            x11::apply_blur(std::ptr::null_mut(), handle.window as _, &[])
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"apply_blur()\" is only supported on Windows, and on X11 with the \"x11\" feature.",
+        )),
+    }
+}
+
+/// Applies blur effect to window with the given options.
+/// Works only on Windows 7, Windows 8, Windows 8.1, Windows 10 v1809 or newer and Windows 11.
+///
+/// See [`apply_blur`] and [`EffectOptions`] for details.
+/// Returns the mechanism the effect was applied with, see [`Applied`].
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn apply_blur_with_options(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] options: EffectOptions,
+) -> Result<Applied, Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::apply_blur(windows::to_hwnd(handle.hwnd), options)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"apply_blur_with_options()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Applies blur effect to the given regions of the window. Works only on Windows 7.
+///
+/// ## Arguments:
+///
+/// - *`rects`* is a list of `(x, y, width, height)` rectangles in client coordinates,
+///   the blur is applied to their union. An empty list blurs the whole window like [`apply_blur`].
+///
+/// Use [`clear_blur`] to disable the effect again.
+///
+/// ## Platform-specific
+///
+/// - **Linux**: Only supported on X11 with KWin and the `x11` feature, see [`apply_blur`].
+///   The rectangles are in physical pixels and can't have negative coordinates.
+/// - **macOS**: Unsupported.
+pub fn apply_blur_region(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] rects: &[(i32, i32, i32, i32)],
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::apply_blur_region(windows::to_hwnd(handle.hwnd), rects)
+        }
+        #[cfg(all(target_os = "linux", feature = "x11"))]
+        raw_window_handle::RawWindowHandle::Xlib(handle) => x11::apply_blur(std::ptr::null_mut(), handle.window, rects),
+        #[cfg(all(target_os = "linux", feature = "x11"))]
+        raw_window_handle::RawWindowHandle::Xcb(handle) => {
+            x11::apply_blur(std::ptr::null_mut(), handle.window as _, rects)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"apply_blur_region()\" is only supported on Windows, and on X11 with the \"x11\" feature.",
+        )),
+    }
+}
+
+/// Clears blur effect applied to window. Works only on Windows 7, Windows 8, Windows 8.1, Windows 10 v1809 or newer and Windows 11.
+///
+/// Returns `true` if an effect applied by this crate was removed, `false` if there was none.
+///
+/// ## Platform-specific
+///
+/// - **Linux**: Only supported on X11 with the `x11` feature, which deletes `_KDE_NET_WM_BLUR_BEHIND_REGION`.
+/// - **macOS**: Unsupported.
+pub fn clear_blur(window: impl raw_window_handle::HasRawWindowHandle) -> Result<bool, Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::clear_blur(windows::to_hwnd(handle.hwnd))
+        }
+        #[cfg(all(target_os = "linux", feature = "x11"))]
+        raw_window_handle::RawWindowHandle::Xlib(handle) => {
+            x11::clear_blur(std::ptr::null_mut(), handle.window)
+        }
+        #[cfg(all(target_os = "linux", feature = "x11"))]
+        raw_window_handle::RawWindowHandle::Xcb(handle) => {
+            x11::clear_blur(std::ptr::null_mut(), handle.window as _)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"clear_blur()\" is only supported on Windows, and on X11 with the \"x11\" feature.",
+        )),
+    }
+}
+
+/// Applies blur effect to the given regions of an X11 window, through the display connection of the window.
+/// Works only on X11 with KWin and the `x11` feature.
+///
+/// [`apply_blur`] and [`apply_blur_region`] only know the window id, so they connect to the display
+/// of the `DISPLAY` environment variable, which is the wrong X server for windows on another display.
+/// This uses the Xlib display of *`window`* instead. XCB connections can't be used through Xlib,
+/// so XCB windows still go through `DISPLAY`.
+///
+/// An empty *`rects`* blurs the whole window, see [`apply_blur_region`] for the rectangles.
+///
+/// ## Platform-specific
+///
+/// - **Windows / macOS**: Unsupported.
+pub fn apply_blur_x11(
+    window: impl raw_window_handle::HasRawWindowHandle + raw_window_handle::HasRawDisplayHandle,
+    #[allow(unused)] rects: &[(i32, i32, i32, i32)],
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(all(target_os = "linux", feature = "x11"))]
+        raw_window_handle::RawWindowHandle::Xlib(handle) => x11::apply_blur(
+            x11::xlib_display(window.raw_display_handle()),
+            handle.window,
+            rects,
+        ),
+        #[cfg(all(target_os = "linux", feature = "x11"))]
+        raw_window_handle::RawWindowHandle::Xcb(handle) => {
+            x11::apply_blur(std::ptr::null_mut(), handle.window as _, rects)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"apply_blur_x11()\" is only supported on X11 with the \"x11\" feature.",
+        )),
+    }
+}
+
+/// Clears blur effect applied to an X11 window, through the display connection of the window.
+/// Works only on X11 with the `x11` feature.
+///
+/// Returns `true` if the blur region was removed, `false` if there was none. See [`apply_blur_x11`].
+///
+/// ## Platform-specific
+///
+/// - **Windows / macOS**: Unsupported.
+pub fn clear_blur_x11(
+    window: impl raw_window_handle::HasRawWindowHandle + raw_window_handle::HasRawDisplayHandle,
+) -> Result<bool, Error> {
+    match window.raw_window_handle() {
+        #[cfg(all(target_os = "linux", feature = "x11"))]
+        raw_window_handle::RawWindowHandle::Xlib(handle) => x11::clear_blur(
+            x11::xlib_display(window.raw_display_handle()),
+            handle.window,
+        ),
+        #[cfg(all(target_os = "linux", feature = "x11"))]
+        raw_window_handle::RawWindowHandle::Xcb(handle) => {
+            x11::clear_blur(std::ptr::null_mut(), handle.window as _)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"clear_blur_x11()\" is only supported on X11 with the \"x11\" feature.",
+        )),
+    }
+}
+
+/// Applies a transparent tint to window, without any blur.
+/// Works only on Windows 10 v1809 or newer and Windows 11.
+///
+/// This is cheaper than [`apply_blur`] and [`apply_acrylic`],
+/// the alpha of *`color`* controls how transparent the window is.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn apply_transparent(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] color: Option<Color>,
 ) -> Result<(), Error> {
     match window.raw_window_handle() {
         #[cfg(target_os = "windows")]
         raw_window_handle::RawWindowHandle::Win32(handle) => {
-            windows::apply_blur(handle.hwnd as _, color)
+            windows::apply_transparent(windows::to_hwnd(handle.hwnd), color)
         }
         _ => Err(Error::UnsupportedPlatform(
-            "\"apply_blur()\" is only supported on Windows.",
+            "\"apply_transparent()\" is only supported on Windows.",
         )),
     }
 }
 
-/// Clears blur effect applied to window. Works only on Windows 7, Windows 10 v1809 or newer and Windows 11.
+/// Clears the transparent tint applied to window. Works only on Windows 10 v1809 or newer and Windows 11.
+///
+/// Returns `true` if an effect applied by this crate was removed, `false` if there was none.
 ///
 /// ## Platform-specific
 ///
 /// - **Linux / macOS**: Unsupported.
-pub fn clear_blur(window: impl raw_window_handle::HasRawWindowHandle) -> Result<(), Error> {
+pub fn clear_transparent(
+    window: impl raw_window_handle::HasRawWindowHandle,
+) -> Result<bool, Error> {
     match window.raw_window_handle() {
         #[cfg(target_os = "windows")]
-        raw_window_handle::RawWindowHandle::Win32(handle) => windows::clear_blur(handle.hwnd as _),
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::clear_transparent(windows::to_hwnd(handle.hwnd))
+        }
         _ => Err(Error::UnsupportedPlatform(
-            "\"clear_blur()\" is only supported on Windows.",
+            "\"clear_transparent()\" is only supported on Windows.",
         )),
     }
 }
 
-/// Applies Acrylic effect to you window. Works only on Windows 10 v1809 or newer and Windows 11
+/// Applies Acrylic effect to you window. Works only on Windows 10 v1709 or newer and Windows 11
 ///
-/// - *`color`* is ignored on Windows 11 build 22523 and newer and has no effect.
+/// - *`color`* is ignored on Windows 11 22H2 (build 22621) and newer and has no effect.
+///   A fully transparent color is drawn with the minimal non-zero alpha instead.
 ///
 /// ## WARNING:
 ///
@@ -79,6 +295,9 @@ pub fn clear_blur(window: impl raw_window_handle::HasRawWindowHandle) -> Result<
 /// It is an issue in the undocumented api used for this method
 /// and microsoft needs to fix it (they probably won't).
 ///
+/// Returns [`Error::LayeredWindow`] for windows with the `WS_EX_LAYERED` extended style,
+/// use [`apply_acrylic_with_options`] with [`EffectOptions::allow_layered`] to apply it anyway.
+///
 /// ## Platform-specific
 ///
 /// - **Linux / macOS**: Unsupported.
@@ -86,27 +305,87 @@ pub fn apply_acrylic(
     window: impl raw_window_handle::HasRawWindowHandle,
     #[allow(unused)] color: Option<Color>,
 ) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => windows::apply_acrylic(
+            windows::to_hwnd(handle.hwnd),
+            EffectOptions {
+                color,
+                ..Default::default()
+            },
+        )
+        .map(|_| ()),
+        _ => Err(Error::UnsupportedPlatform(
+            "\"apply_acrylic()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Applies Acrylic effect to window with the given options.
+/// Works only on Windows 10 v1709 or newer and Windows 11.
+///
+/// See [`apply_acrylic`] and [`EffectOptions`] for details.
+/// Returns the mechanism the effect was applied with, see [`Applied`].
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn apply_acrylic_with_options(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] options: EffectOptions,
+) -> Result<Applied, Error> {
     match window.raw_window_handle() {
         #[cfg(target_os = "windows")]
         raw_window_handle::RawWindowHandle::Win32(handle) => {
-            windows::apply_acrylic(handle.hwnd as _, color)
+            windows::apply_acrylic(windows::to_hwnd(handle.hwnd), options)
         }
         _ => Err(Error::UnsupportedPlatform(
-            "\"apply_acrylic()\" is only supported on Windows.",
+            "\"apply_acrylic_with_options()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Applies Acrylic effect to window like [`apply_acrylic`], and works around its resize lag
+/// by temporarily switching to blur while the window is being resized or dragged.
+/// Works only on Windows 10 v1709 or newer and Windows 11.
+///
+/// The window is subclassed to know when the modal size/move loop starts and ends,
+/// [`clear_acrylic`] and [`apply_acrylic`] remove the workaround again.
+/// Calling this function more than once on the same window never installs more than one subclass.
+/// It must be called from the thread that created the window.
+///
+/// On Windows 11 22H2 and newer, the system backdrop is used which doesn't lag, so this behaves like [`apply_acrylic`].
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn apply_acrylic_with_resize_workaround(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] color: Option<Color>,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::apply_acrylic_with_resize_workaround(windows::to_hwnd(handle.hwnd), color)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"apply_acrylic_with_resize_workaround()\" is only supported on Windows.",
         )),
     }
 }
 
-/// Clears acrylic effect applied to window. Works only on Windows 10 v1809 or newer and Windows 11.
+/// Clears acrylic effect applied to window. Works only on Windows 10 v1709 or newer and Windows 11.
+///
+/// Returns `true` if an effect applied by this crate was removed, `false` if there was none.
 ///
 /// ## Platform-specific
 ///
 /// - **Linux / macOS**: Unsupported.
-pub fn clear_acrylic(window: impl raw_window_handle::HasRawWindowHandle) -> Result<(), Error> {
+pub fn clear_acrylic(window: impl raw_window_handle::HasRawWindowHandle) -> Result<bool, Error> {
     match window.raw_window_handle() {
         #[cfg(target_os = "windows")]
         raw_window_handle::RawWindowHandle::Win32(handle) => {
-            windows::clear_acrylic(handle.hwnd as _)
+            windows::clear_acrylic(windows::to_hwnd(handle.hwnd))
         }
         _ => Err(Error::UnsupportedPlatform(
             "\"clear_acrylic()\" is only supported on Windows.",
@@ -114,15 +393,67 @@ pub fn clear_acrylic(window: impl raw_window_handle::HasRawWindowHandle) -> Resu
     }
 }
 
+/// Applies the host backdrop accent to window, an acrylic-like blur that samples the desktop wallpaper
+/// instead of the windows behind it. Works only on Windows 10 v1903 or newer and Windows 11.
+///
+/// This is unrelated to [`set_host_backdrop_brush`], it only sets the accent of the window.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn apply_host_backdrop_accent(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] color: Option<Color>,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::apply_host_backdrop_accent(windows::to_hwnd(handle.hwnd), color)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"apply_host_backdrop_accent()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Clears the host backdrop accent applied to window. Works only on Windows 10 v1903 or newer and Windows 11.
+///
+/// Returns `true` if an effect applied by this crate was removed, `false` if there was none.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn clear_host_backdrop_accent(
+    window: impl raw_window_handle::HasRawWindowHandle,
+) -> Result<bool, Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::clear_host_backdrop_accent(windows::to_hwnd(handle.hwnd))
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"clear_host_backdrop_accent()\" is only supported on Windows.",
+        )),
+    }
+}
+
 /// Applies mica effect to window. Works only on Windows 11.
 ///
+/// On Windows 11 22H2 and newer, the system backdrop is used. On older Windows 11 builds,
+/// the undocumented `DWMWA_MICA_EFFECT` attribute is used instead.
+///
+/// Windows without decorations also need their frame extended into the client area
+/// with [`extend_frame_into_client_area`] for the effect to be visible.
+///
 /// ## Platform-specific
 ///
 /// - **Linux / macOS**: Unsupported.
 pub fn apply_mica(window: impl raw_window_handle::HasRawWindowHandle) -> Result<(), Error> {
     match window.raw_window_handle() {
         #[cfg(target_os = "windows")]
-        raw_window_handle::RawWindowHandle::Win32(handle) => windows::apply_mica(handle.hwnd as _),
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::apply_mica(windows::to_hwnd(handle.hwnd))
+        }
         _ => Err(Error::UnsupportedPlatform(
             "\"apply_mica()\" is only supported on Windows.",
         )),
@@ -131,55 +462,1778 @@ pub fn apply_mica(window: impl raw_window_handle::HasRawWindowHandle) -> Result<
 
 /// Clears mica effect applied to window. Works only on Windows 11.
 ///
+/// On Windows 11 22H2 and newer, this resets the system backdrop to [`SystemBackdropType::Auto`]
+/// and the `DWMWA_MICA_EFFECT` attribute used on older Windows 11 builds.
+///
+/// Returns `true` if an effect applied by this crate was removed, `false` if there was none.
+///
 /// ## Platform-specific
 ///
 /// - **Linux / macOS**: Unsupported.
-pub fn clear_mica(window: impl raw_window_handle::HasRawWindowHandle) -> Result<(), Error> {
+pub fn clear_mica(window: impl raw_window_handle::HasRawWindowHandle) -> Result<bool, Error> {
     match window.raw_window_handle() {
         #[cfg(target_os = "windows")]
-        raw_window_handle::RawWindowHandle::Win32(handle) => windows::clear_mica(handle.hwnd as _),
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::clear_mica(windows::to_hwnd(handle.hwnd))
+        }
         _ => Err(Error::UnsupportedPlatform(
             "\"clear_mica()\" is only supported on Windows.",
         )),
     }
 }
 
-/// Applies macos vibrancy effect to window. Works only on macOS 10.10 or newer.
+/// Applies tabbed effect to window. Works only on Windows 11 22H2 (build 22621) or newer.
 ///
 /// ## Platform-specific
 ///
-/// - **Linux / Windows**: Unsupported.
-pub fn apply_vibrancy(
+/// - **Linux / macOS**: Unsupported.
+pub fn apply_tabbed(window: impl raw_window_handle::HasRawWindowHandle) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::apply_tabbed(windows::to_hwnd(handle.hwnd))
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"apply_tabbed()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Clears tabbed effect applied to window. Works only on Windows 11 22H2 (build 22621) or newer.
+///
+/// Returns `true` if an effect applied by this crate was removed, `false` if there was none.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn clear_tabbed(window: impl raw_window_handle::HasRawWindowHandle) -> Result<bool, Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::clear_tabbed(windows::to_hwnd(handle.hwnd))
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"clear_tabbed()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Applies the given system backdrop to window. Works only on Windows 11 22H2 (build 22621) or newer.
+///
+/// Use [`SystemBackdropType::Auto`] to let DWM decide the backdrop and
+/// [`SystemBackdropType::None`] to explicitly opt out of any system backdrop.
+///
+/// [`SystemBackdropType::Raw`] skips the crate's own validation and is accepted on any Windows 11 build.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn apply_system_backdrop(
     window: impl raw_window_handle::HasRawWindowHandle,
-    #[allow(unused)] effect: NSVisualEffectMaterial,
-    #[allow(unused)] state: Option<NSVisualEffectState>,
-    #[allow(unused)] radius: Option<f64>,
+    #[allow(unused)] backdrop_type: SystemBackdropType,
 ) -> Result<(), Error> {
     match window.raw_window_handle() {
-        #[cfg(target_os = "macos")]
-        raw_window_handle::RawWindowHandle::AppKit(handle) => {
-            macos::apply_vibrancy(handle.ns_window as _, effect, state, radius)
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::apply_system_backdrop(windows::to_hwnd(handle.hwnd), backdrop_type)
         }
         _ => Err(Error::UnsupportedPlatform(
-            "\"apply_vibrancy()\" is only supported on macOS.",
+            "\"apply_system_backdrop()\" is only supported on Windows.",
         )),
     }
 }
 
-#[derive(Debug)]
-pub enum Error {
-    UnsupportedPlatform(&'static str),
-    UnsupportedPlatformVersion(&'static str),
-    NotMainThread(&'static str),
+/// Enables or disables the immersive dark mode of the window, which affects the titlebar
+/// and how the mica and acrylic effects are rendered.
+/// Works only on Windows 10 v1809 or newer and Windows 11.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn set_immersive_dark_mode(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] dark: bool,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::set_immersive_dark_mode(windows::to_hwnd(handle.hwnd), dark)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"set_immersive_dark_mode()\" is only supported on Windows.",
+        )),
+    }
 }
 
-impl std::fmt::Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Error::UnsupportedPlatform(e)
-            | Error::UnsupportedPlatformVersion(e)
-            | Error::NotMainThread(e) => {
-                write!(f, "{}", e)
+/// Sets the rounded corners preference of the window. Works only on Windows 11.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn set_rounded_corners(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] preference: CornerPreference,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::set_rounded_corners(windows::to_hwnd(handle.hwnd), preference)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"set_rounded_corners()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Sets the color of the window border. Works only on Windows 11.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn set_border_color(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] color: BorderColor,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::set_border_color(windows::to_hwnd(handle.hwnd), color)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"set_border_color()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Sets the color of the window titlebar, `None` resets it to the system default.
+/// The alpha channel of the color is ignored. Works only on Windows 11.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn set_caption_color(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] color: Option<Color>,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::set_caption_color(windows::to_hwnd(handle.hwnd), color)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"set_caption_color()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Sets the color of the window titlebar text, `None` resets it to the system default.
+/// The alpha channel of the color is ignored. Works only on Windows 11.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn set_caption_text_color(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] color: Option<Color>,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::set_caption_text_color(windows::to_hwnd(handle.hwnd), color)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"set_caption_text_color()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Allows the window content to use host backdrop brushes, like the XAML and composition `HostBackdrop` acrylic brushes.
+/// Works only on Windows 10 v2004 or newer and Windows 11.
+///
+/// This only lets brushes drawn by the window content sample what is behind the window,
+/// it doesn't apply any effect by itself and is independent from [`apply_acrylic`].
+/// The window content must be transparent where such a brush is drawn, otherwise it renders black.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn set_host_backdrop_brush(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] enable: bool,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::set_host_backdrop_brush(windows::to_hwnd(handle.hwnd), enable)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"set_host_backdrop_brush()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Returns the system backdrop DWM currently uses for the window. Works only on Windows 11 22H2 (build 22621) or newer.
+///
+/// Values this crate doesn't know about are returned as [`SystemBackdropType::Raw`].
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn get_system_backdrop(
+    window: impl raw_window_handle::HasRawWindowHandle,
+) -> Result<SystemBackdropType, Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::get_system_backdrop(windows::to_hwnd(handle.hwnd))
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"get_system_backdrop()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Extends the window frame into the client area. Works only on Windows.
+///
+/// This is needed for [`apply_mica`] and the other system backdrops to render behind
+/// the client area of windows without decorations, usually with [`Margins::Sheet`].
+/// It is independent from the effect functions and can be called again after DPI or frame changes.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn extend_frame_into_client_area(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] margins: Margins,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::extend_frame_into_client_area(windows::to_hwnd(handle.hwnd), margins)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"extend_frame_into_client_area()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Returns the thickness of the border DWM draws around the window, in pixels at the current DPI
+/// of the window. Works only on Windows 11.
+///
+/// Custom title bars drawn over a frame extended with [`extend_frame_into_client_area`]
+/// can use this to avoid overlapping the visible border.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn get_visible_frame_border_thickness(
+    window: impl raw_window_handle::HasRawWindowHandle,
+) -> Result<u32, Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::get_visible_frame_border_thickness(windows::to_hwnd(handle.hwnd))
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"get_visible_frame_border_thickness()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Sets a DWM window attribute the crate has no dedicated function for. Works only on Windows.
+///
+/// `attribute` is the raw value of the `DWMWINDOWATTRIBUTE`, the crate doesn't check that
+/// the attribute exists on the running Windows version or that `value` has the type DWM expects for it,
+/// DWM rejects the call with [`Error::PlatformError`] in most such cases.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn set_dwm_window_attribute(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] attribute: u32,
+    #[allow(unused)] value: DwmAttributeValue,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::set_dwm_window_attribute(windows::to_hwnd(handle.hwnd), attribute, value)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"set_dwm_window_attribute()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Re-applies the effect last applied by this crate to window whenever the system drops it,
+/// for example when desktop composition is toggled, the theme changes or transparency effects
+/// are turned off and on again. Works only on Windows.
+///
+/// The window is subclassed until [`remove_reapply_hook`] is called or the window is destroyed.
+/// Clearing the effect with one of the `clear_*` functions also clears the record,
+/// so a cleared effect is never brought back.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn install_reapply_hook(
+    window: impl raw_window_handle::HasRawWindowHandle,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::install_reapply_hook(windows::to_hwnd(handle.hwnd))
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"install_reapply_hook()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Removes the hook installed with [`install_reapply_hook`]. Works only on Windows.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn remove_reapply_hook(
+    window: impl raw_window_handle::HasRawWindowHandle,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::remove_reapply_hook(windows::to_hwnd(handle.hwnd))
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"remove_reapply_hook()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Applies blur effect to the window identified by `hwnd`, which may belong to another process.
+/// Works on the same Windows versions as [`apply_blur`].
+///
+/// The handle is checked with `IsWindow` first, returning [`Error::InvalidWindowHandle`]
+/// when it doesn't identify a window and [`Error::AccessDenied`] when the owning process
+/// doesn't allow changing the window.
+///
+/// # Safety
+///
+/// `hwnd` must not be reused for another window while the effect is applied,
+/// the crate keeps track of the applied effect by handle.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub unsafe fn apply_blur_hwnd(
+    #[allow(unused)] hwnd: isize,
+    #[allow(unused)] color: Option<Color>,
+) -> Result<(), Error> {
+    #[cfg(target_os = "windows")]
+    return windows::with_window_handle(hwnd, |hwnd| {
+        windows::apply_blur(
+            hwnd,
+            EffectOptions {
+                color,
+                ..Default::default()
+            },
+        )
+        .map(|_| ())
+    });
+    #[cfg(not(target_os = "windows"))]
+    return Err(Error::UnsupportedPlatform(
+        "\"apply_blur_hwnd()\" is only supported on Windows.",
+    ));
+}
+
+/// Clears the blur effect applied to the window identified by `hwnd`, which may belong to another process.
+/// Works on the same Windows versions as [`clear_blur`].
+///
+/// The handle is checked with `IsWindow` first, returning [`Error::InvalidWindowHandle`]
+/// when it doesn't identify a window and [`Error::AccessDenied`] when the owning process
+/// doesn't allow changing the window.
+///
+/// # Safety
+///
+/// `hwnd` must not be reused for another window while the effect is applied,
+/// the crate keeps track of the applied effect by handle.
+///
+/// Returns `true` if an effect applied by this crate was removed, `false` if there was none.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub unsafe fn clear_blur_hwnd(#[allow(unused)] hwnd: isize) -> Result<bool, Error> {
+    #[cfg(target_os = "windows")]
+    return windows::with_window_handle(hwnd, windows::clear_blur);
+    #[cfg(not(target_os = "windows"))]
+    return Err(Error::UnsupportedPlatform(
+        "\"clear_blur_hwnd()\" is only supported on Windows.",
+    ));
+}
+
+/// Applies Acrylic effect to the window identified by `hwnd`, which may belong to another process.
+/// Works on the same Windows versions as [`apply_acrylic`].
+///
+/// The handle is checked with `IsWindow` first, returning [`Error::InvalidWindowHandle`]
+/// when it doesn't identify a window and [`Error::AccessDenied`] when the owning process
+/// doesn't allow changing the window.
+///
+/// # Safety
+///
+/// `hwnd` must not be reused for another window while the effect is applied,
+/// the crate keeps track of the applied effect by handle.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub unsafe fn apply_acrylic_hwnd(
+    #[allow(unused)] hwnd: isize,
+    #[allow(unused)] color: Option<Color>,
+) -> Result<(), Error> {
+    #[cfg(target_os = "windows")]
+    return windows::with_window_handle(hwnd, |hwnd| {
+        windows::apply_acrylic(
+            hwnd,
+            EffectOptions {
+                color,
+                ..Default::default()
+            },
+        )
+        .map(|_| ())
+    });
+    #[cfg(not(target_os = "windows"))]
+    return Err(Error::UnsupportedPlatform(
+        "\"apply_acrylic_hwnd()\" is only supported on Windows.",
+    ));
+}
+
+/// Clears the Acrylic effect applied to the window identified by `hwnd`, which may belong to another process.
+/// Works on the same Windows versions as [`clear_acrylic`].
+///
+/// The handle is checked with `IsWindow` first, returning [`Error::InvalidWindowHandle`]
+/// when it doesn't identify a window and [`Error::AccessDenied`] when the owning process
+/// doesn't allow changing the window.
+///
+/// # Safety
+///
+/// `hwnd` must not be reused for another window while the effect is applied,
+/// the crate keeps track of the applied effect by handle.
+///
+/// Returns `true` if an effect applied by this crate was removed, `false` if there was none.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub unsafe fn clear_acrylic_hwnd(#[allow(unused)] hwnd: isize) -> Result<bool, Error> {
+    #[cfg(target_os = "windows")]
+    return windows::with_window_handle(hwnd, windows::clear_acrylic);
+    #[cfg(not(target_os = "windows"))]
+    return Err(Error::UnsupportedPlatform(
+        "\"clear_acrylic_hwnd()\" is only supported on Windows.",
+    ));
+}
+
+/// Applies mica effect to the window identified by `hwnd`, which may belong to another process.
+/// Works on the same Windows versions as [`apply_mica`].
+///
+/// The handle is checked with `IsWindow` first, returning [`Error::InvalidWindowHandle`]
+/// when it doesn't identify a window and [`Error::AccessDenied`] when the owning process
+/// doesn't allow changing the window.
+///
+/// # Safety
+///
+/// `hwnd` must not be reused for another window while the effect is applied,
+/// the crate keeps track of the applied effect by handle.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub unsafe fn apply_mica_hwnd(#[allow(unused)] hwnd: isize) -> Result<(), Error> {
+    #[cfg(target_os = "windows")]
+    return windows::with_window_handle(hwnd, windows::apply_mica);
+    #[cfg(not(target_os = "windows"))]
+    return Err(Error::UnsupportedPlatform(
+        "\"apply_mica_hwnd()\" is only supported on Windows.",
+    ));
+}
+
+/// Clears the mica effect applied to the window identified by `hwnd`, which may belong to another process.
+/// Works on the same Windows versions as [`clear_mica`].
+///
+/// The handle is checked with `IsWindow` first, returning [`Error::InvalidWindowHandle`]
+/// when it doesn't identify a window and [`Error::AccessDenied`] when the owning process
+/// doesn't allow changing the window.
+///
+/// # Safety
+///
+/// `hwnd` must not be reused for another window while the effect is applied,
+/// the crate keeps track of the applied effect by handle.
+///
+/// Returns `true` if an effect applied by this crate was removed, `false` if there was none.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub unsafe fn clear_mica_hwnd(#[allow(unused)] hwnd: isize) -> Result<bool, Error> {
+    #[cfg(target_os = "windows")]
+    return windows::with_window_handle(hwnd, windows::clear_mica);
+    #[cfg(not(target_os = "windows"))]
+    return Err(Error::UnsupportedPlatform(
+        "\"clear_mica_hwnd()\" is only supported on Windows.",
+    ));
+}
+
+/// Applies blur effect to window like [`apply_blur`], tinted with the user's accent color.
+/// Works only on Windows 7, Windows 8, Windows 8.1, Windows 10 v1809 or newer and Windows 11.
+///
+/// The tint is ignored on Windows 7, Windows 8 and Windows 8.1.
+///
+/// ## Arguments:
+///
+/// - *`alpha`* is the opacity of the tint, the color itself is read from DWM on every call
+///   so a changed accent color is picked up the next time the effect is applied.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn apply_blur_with_system_tint(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] alpha: u8,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::apply_blur_with_system_tint(windows::to_hwnd(handle.hwnd), alpha)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"apply_blur_with_system_tint()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Applies Acrylic effect to window like [`apply_acrylic`], tinted with the user's accent color.
+/// Works only on Windows 10 v1709 or newer and Windows 11.
+///
+/// The tint is ignored on Windows 11 22H2 and newer, where the system backdrop is used.
+///
+/// ## Arguments:
+///
+/// - *`alpha`* is the opacity of the tint, the color itself is read from DWM on every call
+///   so a changed accent color is picked up the next time the effect is applied.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn apply_acrylic_with_system_tint(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] alpha: u8,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::apply_acrylic_with_system_tint(windows::to_hwnd(handle.hwnd), alpha)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"apply_acrylic_with_system_tint()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Updates the tint color of the Acrylic effect applied to window, without applying the whole effect again.
+/// Works only on Windows 10 v1709 or newer and Windows 11 before 22H2.
+///
+/// Returns [`Error::EffectNotApplied`] if acrylic wasn't applied to the window by this crate.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn set_acrylic_tint(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] color: Color,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::set_acrylic_tint(windows::to_hwnd(handle.hwnd), color)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"set_acrylic_tint()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Applies blur effect to a popup window, like a flyout, a context menu or a tooltip, before it is first shown.
+/// Works only on Windows 7, Windows 8, Windows 8.1, Windows 10 v1809 or newer and Windows 11.
+///
+/// Popups need their frame to be refreshed for the effect to show up on the first frame, instead of a black rectangle.
+/// Call this every time the popup is created, the state the crate keeps for it is dropped when it is destroyed.
+///
+/// See [`apply_blur_with_options`] for details.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn prepare_popup_blur(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] options: EffectOptions,
+) -> Result<Applied, Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::prepare_popup_blur(windows::to_hwnd(handle.hwnd), options)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"prepare_popup_blur()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Applies Acrylic effect to a popup window, like a flyout, a context menu or a tooltip, before it is first shown.
+/// Works only on Windows 10 v1709 or newer and Windows 11.
+///
+/// See [`prepare_popup_blur`] and [`apply_acrylic_with_options`] for details.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn prepare_popup_acrylic(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] options: EffectOptions,
+) -> Result<Applied, Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::prepare_popup_acrylic(windows::to_hwnd(handle.hwnd), options)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"prepare_popup_acrylic()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Sets the undocumented accent policy of window, the mechanism behind blur and acrylic.
+/// Works only on Windows 8 and newer.
+///
+/// This is an escape hatch for accent states and flags the crate doesn't wrap.
+/// The policy is not tracked by the crate, applying or clearing effects may overwrite it.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn set_accent_policy(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] policy: AccentPolicy,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::set_accent_policy(windows::to_hwnd(handle.hwnd), policy)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"set_accent_policy()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Makes the title bar and the mica effect of window follow the dark or light apps theme of the user,
+/// including when it changes later on. Works only on Windows 10 v1809 or newer and Windows 11.
+///
+/// The window is subclassed until [`unfollow_system_theme`] is called or the window is destroyed,
+/// the crate never installs more than one subclass on the same window.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn follow_system_theme(
+    window: impl raw_window_handle::HasRawWindowHandle,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::follow_system_theme(windows::to_hwnd(handle.hwnd))
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"follow_system_theme()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Stops following the apps theme of the user, see [`follow_system_theme`]. Works only on Windows.
+///
+/// The immersive dark mode is left as it currently is.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn unfollow_system_theme(
+    window: impl raw_window_handle::HasRawWindowHandle,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::unfollow_system_theme(windows::to_hwnd(handle.hwnd))
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"unfollow_system_theme()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Applies mica effect to a window without decorations. Works only on Windows 11.
+///
+/// This extends the frame into the whole client area, applies [`apply_mica`] and makes
+/// the window redraw its frame, which is what undecorated windows need for mica to be visible.
+/// If `dark` is `Some`, [`set_immersive_dark_mode`] is called first.
+///
+/// Each step fails with the [`Error`] of its own call, for example
+/// [`Error::PlatformError`] with `DwmExtendFrameIntoClientArea` or `SetWindowPos`.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn apply_mica_frameless(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] dark: Option<bool>,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::apply_mica_frameless(windows::to_hwnd(handle.hwnd), dark)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"apply_mica_frameless()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Clears mica effect applied with [`apply_mica_frameless`]. Works only on Windows 11.
+///
+/// This clears the effect, resets the frame extended into the client area and makes
+/// the window redraw its frame. The dark mode set by [`apply_mica_frameless`] is left as is.
+///
+/// Returns `true` if an effect applied by this crate was removed, `false` if there was none.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn clear_mica_frameless(
+    window: impl raw_window_handle::HasRawWindowHandle,
+) -> Result<bool, Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::clear_mica_frameless(windows::to_hwnd(handle.hwnd))
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"clear_mica_frameless()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Cloaks or uncloaks the window. Works only on Windows 8 and newer.
+///
+/// A cloaked window is not drawn by DWM but otherwise behaves as if it was visible,
+/// which can be used to hide the window until its first frame is ready.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn set_cloaked(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] cloaked: bool,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::set_cloaked(windows::to_hwnd(handle.hwnd), cloaked)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"set_cloaked()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Applies mica effect to window while it is cloaked. Works only on Windows 11.
+///
+/// The window is cloaked, [`apply_mica`] is called and the window is uncloaked once DWM
+/// composed a frame, so a newly created window never shows its opaque client area.
+/// The window is uncloaked even if the effect couldn't be applied.
+///
+/// Use [`clear_mica`] to clear the effect.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn apply_mica_cloaked(window: impl raw_window_handle::HasRawWindowHandle) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::apply_mica_cloaked(windows::to_hwnd(handle.hwnd))
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"apply_mica_cloaked()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Enables or disables the rendering of the non-client area by DWM. Works only on Windows.
+///
+/// Disabling it removes the border line DWM draws on top of windows which draw their own frame,
+/// but it also removes the shadow and the frame extended with [`extend_frame_into_client_area`],
+/// so backdrops like [`apply_mica`] are no longer visible behind the client area.
+/// Enabling it again restores the default policy, which depends on the window style, and extends the frame
+/// again by the explicit margins last given to [`extend_frame_into_client_area`], scaled to the current DPI.
+/// Call [`extend_frame_into_client_area`] with [`Margins::Sheet`] again after enabling it, if that was used.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn set_nc_rendering_policy(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] enabled: bool,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::set_nc_rendering_policy(windows::to_hwnd(handle.hwnd), enabled)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"set_nc_rendering_policy()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Applies blur effect to window from any thread of the process.
+/// Works only on Windows 7, Windows 8, Windows 8.1, Windows 10 v1809 or newer and Windows 11.
+///
+/// If this is called from another thread than the one that created the window, the call is sent
+/// to the thread of the window and this waits for it, so that thread must be pumping messages.
+/// Fails with [`Error::WrongThread`] if the window belongs to another process.
+///
+/// See [`apply_blur_with_options`] for details.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn apply_blur_cross_thread(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] options: EffectOptions,
+) -> Result<Applied, Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::apply_blur_cross_thread(windows::to_hwnd(handle.hwnd), options)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"apply_blur_cross_thread()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Scales the explicit margins given to [`extend_frame_into_client_area`] whenever the window moves
+/// to a monitor with another DPI. Works only on Windows.
+///
+/// The margins are kept in the pixels they were given in along with the DPI of the window at that time,
+/// so repeated DPI changes don't accumulate rounding errors. The region given to [`apply_blur_region`]
+/// isn't scaled: it is only supported on Windows 7, whose DPI doesn't change while the user is signed in.
+/// The window is subclassed until [`remove_dpi_rescale_hook`] is called or the window is destroyed.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn install_dpi_rescale_hook(
+    window: impl raw_window_handle::HasRawWindowHandle,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::install_dpi_rescale_hook(windows::to_hwnd(handle.hwnd))
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"install_dpi_rescale_hook()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Removes the hook installed with [`install_dpi_rescale_hook`]. Works only on Windows.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn remove_dpi_rescale_hook(
+    window: impl raw_window_handle::HasRawWindowHandle,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::remove_dpi_rescale_hook(windows::to_hwnd(handle.hwnd))
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"remove_dpi_rescale_hook()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Temporarily disables or enables again the blur applied to window. Works only on Windows 7.
+///
+/// Unlike [`clear_blur`], this keeps the region given to [`apply_blur_region`],
+/// so enabling the blur again doesn't need the region to be given again.
+/// While disabled, the blur is neither re-applied by [`install_reapply_hook`] nor when the window is restored.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn set_blur_enabled(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] enabled: bool,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "windows")]
+        raw_window_handle::RawWindowHandle::Win32(handle) => {
+            windows::set_blur_enabled(windows::to_hwnd(handle.hwnd), enabled)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"set_blur_enabled()\" is only supported on Windows.",
+        )),
+    }
+}
+
+/// Applies macos vibrancy effect to window. Works only on macOS 10.10 or newer.
+///
+/// ## Arguments:
+///
+/// - *`effect`* is the material of the effect view.
+/// - *`state`* is whether the effect view is active, `None` makes it follow the active state of the window
+///   like [`NSVisualEffectState::FollowsWindowActiveState`]. Use [`NSVisualEffectState::Active`] for windows
+///   that shouldn't dim when they lose focus, like palettes. Non-activating panels are always active by default,
+///   since they never become the key window.
+/// - *`radius`* is the corner radius of the effect view, `None` or `0.0` means square corners.
+///
+/// Calling this again updates the effect view added by the previous call instead of adding another one.
+///
+/// The effect view covers the content view, which is the whole window for borderless windows and panels,
+/// and everything below the title bar for titled windows. If the window has a `contentViewController`,
+/// like a SwiftUI `NSHostingController`, the effect view is added behind the content view instead of into it,
+/// since the controller manages the subviews of its view. The content view must not draw an opaque background then.
+///
+/// Panels shown below a status item can be made vibrant before they are shown for the first time,
+/// see the `statusbar` example. The window of the status item itself belongs to the menu bar and
+/// returns [`Error::InvalidArgument`].
+///
+/// ## Platform-specific
+///
+/// - **Linux / Windows**: Unsupported.
+pub fn apply_vibrancy(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    effect: NSVisualEffectMaterial,
+    state: Option<NSVisualEffectState>,
+    radius: Option<f64>,
+) -> Result<(), Error> {
+    apply_vibrancy_with_options(
+        window,
+        effect,
+        VibrancyOptions {
+            state,
+            radius,
+            ..Default::default()
+        },
+    )
+    .map(|_| ())
+}
+
+/// Applies macos vibrancy effect to window with the given options. Works only on macOS 10.10 or newer.
+///
+/// See [`VibrancyOptions`] for the available options and [`apply_vibrancy`] for details.
+///
+/// Returns [`Applied::VisualEffectView`], or [`Applied::SkippedReducedTransparency`]
+/// if nothing was applied, see [`VibrancyOptions::skip_when_transparency_reduced`].
+///
+/// ## Platform-specific
+///
+/// - **Linux / Windows**: Unsupported.
+pub fn apply_vibrancy_with_options(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] effect: NSVisualEffectMaterial,
+    #[allow(unused)] options: VibrancyOptions,
+) -> Result<Applied, Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => {
+            macos::apply_vibrancy(macos::ns_window(handle)?, effect, options)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"apply_vibrancy_with_options()\" is only supported on macOS.",
+        )),
+    }
+}
+
+/// Clears the macos vibrancy effect applied to window with [`apply_vibrancy`]. Works only on macOS 10.10 or newer.
+///
+/// Only the effect views added by this crate are removed, including the ones of [`apply_vibrancy_region`],
+/// effect views added by the application are left as is. The background changed by [`prepare_transparent_window`]
+/// is restored as well.
+///
+/// Returns `true` if an effect applied by this crate was removed, `false` if there was none.
+///
+/// ## Platform-specific
+///
+/// - **Linux / Windows**: Unsupported.
+pub fn clear_vibrancy(window: impl raw_window_handle::HasRawWindowHandle) -> Result<bool, Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => {
+            macos::clear_vibrancy(macos::ns_window(handle)?)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"clear_vibrancy()\" is only supported on macOS.",
+        )),
+    }
+}
+
+/// Sets whether the effect view added by [`apply_vibrancy`] is emphasized, which makes materials
+/// like [`NSVisualEffectMaterial::Sidebar`] look like a selected or focused sidebar. Works only on macOS 10.12 or newer.
+///
+/// This updates the effect view in place, so it can follow the focus of the window.
+///
+/// ## Platform-specific
+///
+/// - **Linux / Windows**: Unsupported.
+pub fn set_vibrancy_emphasized(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] emphasized: bool,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => {
+            macos::set_vibrancy_emphasized(macos::ns_window(handle)?, emphasized)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"set_vibrancy_emphasized()\" is only supported on macOS.",
+        )),
+    }
+}
+
+/// Sets the mask of the effect view added by [`apply_vibrancy`], which shapes the effect
+/// like a rounded card or a chat bubble. Works only on macOS 10.10 or newer.
+///
+/// The mask is stretched with the effect view when the window is resized, except for its cap insets.
+/// `None` removes the mask.
+///
+/// ## Platform-specific
+///
+/// - **Linux / Windows**: Unsupported.
+pub fn set_vibrancy_mask(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] mask: Option<&VibrancyMask>,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => {
+            macos::set_vibrancy_mask(macos::ns_window(handle)?, mask)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"set_vibrancy_mask()\" is only supported on macOS.",
+        )),
+    }
+}
+
+/// Applies macos vibrancy effect to window from any thread. Works only on macOS 10.10 or newer.
+///
+/// If this isn't called on the main thread, the effect is applied on the main thread
+/// and this waits for it, so the main thread must not be waiting for the calling thread.
+/// See [`apply_vibrancy_with_options`] for details.
+///
+/// ## Platform-specific
+///
+/// - **Linux / Windows**: Unsupported.
+pub fn apply_vibrancy_on_main(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] effect: NSVisualEffectMaterial,
+    #[allow(unused)] options: VibrancyOptions,
+) -> Result<Applied, Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => {
+            macos::apply_vibrancy_on_main(macos::ns_window(handle)?, effect, options)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"apply_vibrancy_on_main()\" is only supported on macOS.",
+        )),
+    }
+}
+
+/// Sets the appearance of window, which the title bar and the effect view of [`apply_vibrancy`] follow.
+/// Works only on macOS 10.10 or newer, and macOS 10.14 or newer for [`Appearance::Dark`].
+///
+/// [`Appearance::System`] makes the window follow the system appearance again.
+///
+/// ## Platform-specific
+///
+/// - **Linux / Windows**: Unsupported.
+pub fn set_window_appearance(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] appearance: Appearance,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => {
+            macos::set_window_appearance(macos::ns_window(handle)?, appearance)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"set_window_appearance()\" is only supported on macOS.",
+        )),
+    }
+}
+
+/// Returns the material of the effect view inserted by [`apply_vibrancy`],
+/// or `None` if the crate didn't apply vibrancy to the window.
+/// Works only on macOS 10.10 or newer.
+///
+/// Effect views the application added to the window itself are ignored.
+/// Deprecated materials replaced on macOS 10.14+ are reported as their modern equivalent,
+/// see [`NSVisualEffectMaterial::modern_equivalent`].
+///
+/// ## Platform-specific
+///
+/// - **Linux / Windows**: Unsupported.
+pub fn get_vibrancy(
+    window: impl raw_window_handle::HasRawWindowHandle,
+) -> Result<Option<NSVisualEffectMaterial>, Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => {
+            macos::get_vibrancy(macos::ns_window(handle)?)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"get_vibrancy()\" is only supported on macOS.",
+        )),
+    }
+}
+
+/// Applies macos vibrancy effect to a region of the window, like [`apply_vibrancy_with_options`]
+/// but with one effect view per `region`. Works only on macOS 10.10 or newer.
+///
+/// This combines several materials in one window, for example [`NSVisualEffectMaterial::Sidebar`]
+/// on the left with [`VibrancyAnchor::Left`] and [`NSVisualEffectMaterial::HeaderView`] under the toolbar
+/// with [`VibrancyAnchor::Top`], each with its own [`VibrancyOptions::frame`].
+/// Calling this again with the same `region` updates its effect view.
+///
+/// The effect views of regions are separate from the one of [`apply_vibrancy`].
+/// Use [`clear_vibrancy_region`] to remove one of them, [`clear_vibrancy`] removes all of them.
+///
+/// ## Platform-specific
+///
+/// - **Linux / Windows**: Unsupported.
+pub fn apply_vibrancy_region(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] region: &str,
+    #[allow(unused)] effect: NSVisualEffectMaterial,
+    #[allow(unused)] options: VibrancyOptions,
+) -> Result<Applied, Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => {
+            macos::apply_vibrancy_region(macos::ns_window(handle)?, region, effect, options)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"apply_vibrancy_region()\" is only supported on macOS.",
+        )),
+    }
+}
+
+/// Clears the macos vibrancy effect applied to a region of the window with [`apply_vibrancy_region`].
+/// Works only on macOS 10.10 or newer.
+///
+/// Returns `true` if the effect view of the region was removed, `false` if there was none.
+///
+/// ## Platform-specific
+///
+/// - **Linux / Windows**: Unsupported.
+pub fn clear_vibrancy_region(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] region: &str,
+) -> Result<bool, Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => {
+            macos::clear_vibrancy_region(macos::ns_window(handle)?, region)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"clear_vibrancy_region()\" is only supported on macOS.",
+        )),
+    }
+}
+
+/// Sets whether the content view of the window allows vibrancy, which blends what it draws
+/// with the effect view of [`apply_vibrancy`] behind it. Works only on macOS 10.10 or newer.
+///
+/// `allowsVibrancy` is a read-only property views override, so this replaces the class of the content view
+/// with a subclass returning `true`, and `false` restores the original class. The frame, the layout and the subviews
+/// of the content view are left as they are. Subviews decide for themselves, a webview added to the
+/// content view for example keeps drawing without vibrancy.
+///
+/// Returns [`Error::InvalidArgument`] if the class of the content view is already replaced at runtime,
+/// like when it is observed with key-value observing.
+///
+/// ## Platform-specific
+///
+/// - **Linux / Windows**: Unsupported.
+pub fn set_content_allows_vibrancy(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] allows_vibrancy: bool,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => {
+            macos::set_content_allows_vibrancy(macos::ns_window(handle)?, allows_vibrancy)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"set_content_allows_vibrancy()\" is only supported on macOS.",
+        )),
+    }
+}
+
+/// Makes the title bar of the window transparent and extends the content view below it,
+/// so the effect view of [`apply_vibrancy`] covers the title bar too. Works only on macOS 10.10 or newer.
+///
+/// This sets `titlebarAppearsTransparent` and the `NSWindowStyleMaskFullSizeContentView` style mask,
+/// and hides the title if *`hide_title`* is `true`. [`restore_titlebar`] undoes it.
+///
+/// ## Platform-specific
+///
+/// - **Linux / Windows**: Unsupported.
+pub fn make_titlebar_transparent(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] hide_title: bool,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => {
+            macos::make_titlebar_transparent(macos::ns_window(handle)?, hide_title)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"make_titlebar_transparent()\" is only supported on macOS.",
+        )),
+    }
+}
+
+/// Restores the title bar changed by [`make_titlebar_transparent`] to the defaults: an opaque title bar,
+/// a visible title and a content view below the title bar. Works only on macOS 10.10 or newer.
+///
+/// ## Platform-specific
+///
+/// - **Linux / Windows**: Unsupported.
+pub fn restore_titlebar(window: impl raw_window_handle::HasRawWindowHandle) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => {
+            macos::restore_titlebar(macos::ns_window(handle)?)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"restore_titlebar()\" is only supported on macOS.",
+        )),
+    }
+}
+
+/// Changes the material of the effect view added by [`apply_vibrancy`] with a crossfade
+/// lasting *`duration`*. Works only on macOS 10.10 or newer.
+///
+/// The material changes right away if *`duration`* is zero or the user enabled "Reduce motion"
+/// in the accessibility settings. If this isn't called on the main thread, the material is changed
+/// on the main thread and this waits for it, like [`apply_vibrancy_on_main`].
+///
+/// ## Platform-specific
+///
+/// - **Linux / Windows**: Unsupported.
+pub fn set_vibrancy_animated(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] effect: NSVisualEffectMaterial,
+    #[allow(unused)] duration: std::time::Duration,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => {
+            macos::set_vibrancy_animated(macos::ns_window(handle)?, effect, duration)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"set_vibrancy_animated()\" is only supported on macOS.",
+        )),
+    }
+}
+
+/// Applies macos vibrancy effect to the title bar and toolbar area of window only,
+/// leaving the rest of the content view as it is. Works only on macOS 10.11 or newer.
+///
+/// The effect view is pinned to the top of the content view and ends where the content layout rect starts,
+/// so it follows the toolbar when it is shown or hidden. The content view must extend below the title bar,
+/// see [`make_titlebar_transparent`], [`Error::InvalidArgument`] is returned otherwise.
+///
+/// This uses the region `"titlebar"` of [`apply_vibrancy_region`], [`clear_vibrancy_region`] removes it.
+///
+/// ## Platform-specific
+///
+/// - **Linux / Windows**: Unsupported.
+pub fn apply_titlebar_vibrancy(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] effect: NSVisualEffectMaterial,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => {
+            macos::apply_titlebar_vibrancy(macos::ns_window(handle)?, effect)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"apply_titlebar_vibrancy()\" is only supported on macOS.",
+        )),
+    }
+}
+
+/// Makes the background of window clear and the window non-opaque, which the effect view of [`apply_vibrancy`]
+/// needs to show the desktop behind the window. Works only on macOS 10.10 or newer.
+///
+/// The previous background color and opacity are restored by [`clear_vibrancy`].
+/// This isn't done by [`apply_vibrancy`] itself, since windowing libraries like tao and winit
+/// manage both for transparent windows already.
+///
+/// ## Platform-specific
+///
+/// - **Linux / Windows**: Unsupported.
+pub fn prepare_transparent_window(
+    window: impl raw_window_handle::HasRawWindowHandle,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => {
+            macos::prepare_transparent_window(macos::ns_window(handle)?)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"prepare_transparent_window()\" is only supported on macOS.",
+        )),
+    }
+}
+
+/// Calls *`callback`* with [`Appearance::Dark`] or [`Appearance::Light`] whenever the effective appearance
+/// of window changes, like when the user switches between the light and the dark mode. Works only on macOS 10.14 or newer.
+///
+/// The callback is called on a thread of the observer, so it doesn't block the main thread, and with
+/// the changes in the order they happened.
+/// Observing stops when the returned [`ObserverHandle`] is dropped or when the window is closed.
+///
+/// ## Platform-specific
+///
+/// - **Linux / Windows**: Unsupported.
+pub fn observe_appearance_changes(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] callback: impl Fn(Appearance) + Send + 'static,
+) -> Result<ObserverHandle, Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => {
+            macos::observe_appearance_changes(macos::ns_window(handle)?, Box::new(callback))
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"observe_appearance_changes()\" is only supported on macOS.",
+        )),
+    }
+}
+
+/// Returns the `NSVisualEffectView` added by [`apply_vibrancy`], or `None` if the crate didn't apply vibrancy to the window.
+/// Works only on macOS 10.10 or newer.
+///
+/// This is meant for customizing the view beyond what this crate offers, like accessibility attributes
+/// or layer filters. The view is still owned by this crate: it isn't retained for the caller, it is removed
+/// by [`clear_vibrancy`], and functions like [`apply_vibrancy`] may overwrite changes made to it.
+/// Don't use the pointer after vibrancy was cleared, and don't remove the view from its superview yourself.
+///
+/// ## Platform-specific
+///
+/// - **Linux / Windows**: Unsupported.
+pub fn get_vibrancy_view(
+    window: impl raw_window_handle::HasRawWindowHandle,
+) -> Result<Option<std::ptr::NonNull<std::ffi::c_void>>, Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => {
+            macos::get_vibrancy_view(macos::ns_window(handle)?)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"get_vibrancy_view()\" is only supported on macOS.",
+        )),
+    }
+}
+
+/// Sets the intensity of the effect view added by [`apply_vibrancy`] from `0.0` to `1.0`,
+/// which blends the effect with what is behind it. Works only on macOS 10.10 or newer.
+///
+/// Values out of range are clamped, `NaN` returns [`Error::InvalidArgument`].
+///
+/// ## Platform-specific
+///
+/// - **Linux / Windows**: Unsupported.
+pub fn set_vibrancy_alpha(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] alpha: f64,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => {
+            macos::set_vibrancy_alpha(macos::ns_window(handle)?, alpha)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"set_vibrancy_alpha()\" is only supported on macOS.",
+        )),
+    }
+}
+
+/// Sets the color drawn over the effect view added by [`apply_vibrancy`], like the color of [`apply_acrylic`]
+/// on Windows, without changing the material. Works only on macOS 10.10 or newer.
+///
+/// `None` removes the tint.
+///
+/// ## Platform-specific
+///
+/// - **Linux / Windows**: Unsupported.
+pub fn set_vibrancy_tint(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] tint: Option<Color>,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => {
+            macos::set_vibrancy_tint(macos::ns_window(handle)?, tint)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"set_vibrancy_tint()\" is only supported on macOS.",
+        )),
+    }
+}
+
+/// Moves the effect views added by [`apply_vibrancy`] back behind all other subviews of the content view.
+/// Works only on macOS 10.10 or newer.
+///
+/// Effect views are added behind the existing subviews, but code reordering the subviews afterwards
+/// can move them in front of views added later, like the webview of a Tauri or wry window.
+/// Call this after creating such views to make sure they aren't covered.
+/// Effect views placed with [`VibrancyPlacement::Above`] are left where they are.
+///
+/// ## Platform-specific
+///
+/// - **Linux / Windows**: Unsupported.
+pub fn ensure_vibrancy_behind_content(
+    window: impl raw_window_handle::HasRawWindowHandle,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => {
+            macos::ensure_vibrancy_behind_content(macos::ns_window(handle)?)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"ensure_vibrancy_behind_content()\" is only supported on macOS.",
+        )),
+    }
+}
+
+/// Returns whether the user turned on "Reduce transparency" in the accessibility settings of macOS,
+/// which draws the effect view of [`apply_vibrancy`] as an opaque gray.
+///
+/// ## Platform-specific
+///
+/// - **Linux / Windows**: Always returns `false`.
+pub fn is_transparency_reduced() -> bool {
+    #[cfg(target_os = "macos")]
+    return macos::is_transparency_reduced();
+    #[cfg(not(target_os = "macos"))]
+    return false;
+}
+
+/// Calls *`callback`* with [`is_transparency_reduced`] whenever the accessibility display options of macOS change,
+/// so the application can switch to an opaque theme while it's running. Works only on macOS 10.10 or newer.
+///
+/// The callback is called on a thread of the observer, so it doesn't block the main thread, and with
+/// the changes in the order they happened. It may be called
+/// without the value changing, since other display options like "Reduce motion" are reported together.
+/// Observing stops when the returned [`ObserverHandle`] is dropped.
+///
+/// ## Platform-specific
+///
+/// - **Linux / Windows**: Unsupported.
+pub fn observe_transparency_changes(
+    #[allow(unused)] callback: impl Fn(bool) + Send + 'static,
+) -> Result<ObserverHandle, Error> {
+    #[cfg(target_os = "macos")]
+    return macos::observe_transparency_changes(Box::new(callback));
+    #[cfg(not(target_os = "macos"))]
+    return Err(Error::UnsupportedPlatform(
+        "\"observe_transparency_changes()\" is only supported on macOS.",
+    ));
+}
+
+/// Sets whether window has a shadow. Works only on macOS 10.10 or newer.
+///
+/// Translucent windows with rounded effect views get a dark outline from the shadow of the window,
+/// which `false` removes. The shadow follows the current content of the window, [`apply_vibrancy`]
+/// updates it after adding the effect view.
+///
+/// ## Platform-specific
+///
+/// - **Linux / Windows**: Unsupported.
+pub fn set_window_shadow(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] shadow: bool,
+) -> Result<(), Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => {
+            macos::set_window_shadow(macos::ns_window(handle)?, shadow)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"set_window_shadow()\" is only supported on macOS.",
+        )),
+    }
+}
+
+/// Applies *`material`* to every sheet presented on the window from now on, like [`apply_vibrancy`] does.
+/// Works only on macOS 10.10 or newer.
+///
+/// Save and open panels are left out, since they draw their own background.
+/// Presenting a sheet again updates the effect view it got before instead of adding another one.
+/// Propagating stops when the returned [`ObserverHandle`] is dropped or when the window is closed.
+///
+/// ## Platform-specific
+///
+/// - **Linux / Windows**: Unsupported.
+pub fn propagate_to_sheets(
+    window: impl raw_window_handle::HasRawWindowHandle,
+    #[allow(unused)] material: NSVisualEffectMaterial,
+) -> Result<ObserverHandle, Error> {
+    match window.raw_window_handle() {
+        #[cfg(target_os = "macos")]
+        raw_window_handle::RawWindowHandle::AppKit(handle) => {
+            macos::propagate_to_sheets(macos::ns_window(handle)?, material)
+        }
+        _ => Err(Error::UnsupportedPlatform(
+            "\"propagate_to_sheets()\" is only supported on macOS.",
+        )),
+    }
+}
+
+/// Returns whether [`apply_blur`] is supported on the running system.
+///
+/// This performs the same checks as [`apply_blur`] itself without needing a window,
+/// including whether desktop composition is enabled on Windows 7.
+pub fn is_blur_supported() -> bool {
+    #[cfg(target_os = "windows")]
+    return windows::supports_blur();
+    #[cfg(not(target_os = "windows"))]
+    return false;
+}
+
+/// Returns whether [`apply_acrylic`] is supported on the running system.
+///
+/// This performs the same checks as [`apply_acrylic`] itself without needing a window.
+pub fn is_acrylic_supported() -> bool {
+    #[cfg(target_os = "windows")]
+    return windows::supports_acrylic();
+    #[cfg(not(target_os = "windows"))]
+    return false;
+}
+
+/// Returns whether [`apply_mica`] is supported on the running system.
+///
+/// This performs the same checks as [`apply_mica`] itself without needing a window.
+pub fn is_mica_supported() -> bool {
+    #[cfg(target_os = "windows")]
+    return windows::supports_mica();
+    #[cfg(not(target_os = "windows"))]
+    return false;
+}
+
+/// Returns whether [`apply_tabbed`] is supported on the running system.
+///
+/// This performs the same checks as [`apply_tabbed`] itself without needing a window.
+pub fn is_tabbed_supported() -> bool {
+    #[cfg(target_os = "windows")]
+    return windows::supports_tabbed();
+    #[cfg(not(target_os = "windows"))]
+    return false;
+}
+
+/// Returns whether the user has "Transparency effects" turned on in the Windows settings.
+///
+/// The setting is also turned off while battery saver is on. Effects applied while it is off
+/// are drawn as a solid color, see [`EffectOptions::skip_when_transparency_disabled`] to skip them instead.
+///
+/// ## Platform-specific
+///
+/// - **Windows 7 / 8**: Always returns `true`, the setting doesn't exist.
+/// - **Linux / macOS**: Always returns `true`.
+pub fn are_transparency_effects_enabled() -> bool {
+    #[cfg(target_os = "windows")]
+    return windows::transparency_effects_enabled();
+    #[cfg(not(target_os = "windows"))]
+    return true;
+}
+
+/// Returns whether a high contrast theme is active.
+///
+/// Translucent backdrops hurt readability under high contrast themes and should be turned off,
+/// see [`EffectOptions::skip_when_high_contrast`] to skip them automatically.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Always returns `false`.
+pub fn is_high_contrast_enabled() -> bool {
+    #[cfg(target_os = "windows")]
+    return windows::high_contrast_enabled();
+    #[cfg(not(target_os = "windows"))]
+    return false;
+}
+
+/// Returns whether the user chose the dark apps theme in the Windows settings.
+///
+/// A missing setting means the light theme, which is also the only theme before Windows 10 v1809.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn is_dark_mode_enabled() -> Result<bool, Error> {
+    #[cfg(target_os = "windows")]
+    return Ok(windows::dark_mode_enabled());
+    #[cfg(not(target_os = "windows"))]
+    return Err(Error::UnsupportedPlatform(
+        "\"is_dark_mode_enabled()\" is only supported on Windows.",
+    ));
+}
+
+/// Returns the accent color the user chose in the Windows settings.
+///
+/// The color is read again on every call, since the user can change it at any time.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn get_system_accent_color() -> Result<Color, Error> {
+    #[cfg(target_os = "windows")]
+    return windows::system_accent_color();
+    #[cfg(not(target_os = "windows"))]
+    return Err(Error::UnsupportedPlatform(
+        "\"get_system_accent_color()\" is only supported on Windows.",
+    ));
+}
+
+/// Returns whether the user chose to show the accent color on title bars and window borders.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Unsupported.
+pub fn is_accent_color_on_title_bars() -> Result<bool, Error> {
+    #[cfg(target_os = "windows")]
+    return Ok(windows::accent_color_on_title_bars());
+    #[cfg(not(target_os = "windows"))]
+    return Err(Error::UnsupportedPlatform(
+        "\"is_accent_color_on_title_bars()\" is only supported on Windows.",
+    ));
+}
+
+/// Makes the crate behave as if it was running on the given Windows build number,
+/// `None` goes back to the detected one.
+///
+/// This is meant for testing how an application behaves with the effects available on other Windows versions,
+/// the effects themselves still depend on what the running system actually supports.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Has no effect.
+pub fn override_windows_build_number(#[allow(unused)] build: Option<u32>) {
+    #[cfg(target_os = "windows")]
+    windows::override_build_number(build);
+}
+
+/// Returns the build number of the running Windows system, like `22621` for Windows 11 22H2.
+///
+/// This is the build number the crate uses to decide which effects are available,
+/// so it reflects [`override_windows_build_number`]. It is queried once and cached.
+///
+/// ## Platform-specific
+///
+/// - **Linux / macOS**: Always returns `None`.
+pub fn windows_build_number() -> Option<u32> {
+    #[cfg(target_os = "windows")]
+    return windows::build_number();
+    #[cfg(not(target_os = "windows"))]
+    return None;
+}
+
+/// Returns the version of the running macOS system as `(major, minor, patch)`, like `(13, 2, 1)`.
+///
+/// This is the version the crate uses to decide which materials and functions are available.
+/// It comes from `NSProcessInfo.operatingSystemVersion`, so applications built with an older SDK
+/// may see macOS 11 and newer as `(10, 16, 0)`.
+///
+/// ## Platform-specific
+///
+/// - **Linux / Windows**: Always returns `None`.
+pub fn macos_version() -> Option<(u64, u64, u64)> {
+    #[cfg(target_os = "macos")]
+    return Some(macos::macos_version());
+    #[cfg(not(target_os = "macos"))]
+    return None;
+}
+
+#[derive(Debug)]
+pub enum Error {
+    UnsupportedPlatform(&'static str),
+    UnsupportedPlatformVersion(&'static str),
+    NotMainThread(&'static str),
+    /// Desktop composition is disabled, which is possible on Windows 7 with the basic or classic theme.
+    DwmCompositionDisabled(&'static str),
+    /// The raw window handle doesn't identify an existing window.
+    InvalidWindowHandle(&'static str),
+    /// The window belongs to a process that doesn't allow changing it.
+    AccessDenied(&'static str),
+    /// The window has the `WS_EX_LAYERED` extended style, see [`EffectOptions::allow_layered`].
+    LayeredWindow(&'static str),
+    /// The window is a child window, effects can only be applied to top-level windows.
+    NotTopLevelWindow(&'static str),
+    /// The window has the `WS_EX_NOREDIRECTIONBITMAP` extended style, usually because it is rendered
+    /// with DirectComposition, and the effect would turn its client area black.
+    /// System backdrops don't rely on the redirection surface, see [`apply_system_backdrop`].
+    NoRedirectionBitmap(&'static str),
+    /// The effect the call operates on is not applied to the window.
+    EffectNotApplied(&'static str),
+    /// An argument of the call is invalid.
+    InvalidArgument(&'static str),
+    /// The call can't be made from the current thread, or couldn't be run on the thread of the window.
+    WrongThread(&'static str),
+    /// The window has no content view yet, which happens early in the construction of some windows.
+    NoWindowContentView(&'static str),
+    /// A platform API call failed, `code` is the error code returned by `call`.
+    PlatformError {
+        call: &'static str,
+        code: i32,
+    },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::UnsupportedPlatform(e)
+            | Error::UnsupportedPlatformVersion(e)
+            | Error::NotMainThread(e)
+            | Error::DwmCompositionDisabled(e)
+            | Error::InvalidWindowHandle(e)
+            | Error::AccessDenied(e)
+            | Error::LayeredWindow(e)
+            | Error::NotTopLevelWindow(e)
+            | Error::NoRedirectionBitmap(e)
+            | Error::EffectNotApplied(e)
+            | Error::InvalidArgument(e)
+            | Error::WrongThread(e)
+            | Error::NoWindowContentView(e) => {
+                write!(f, "{}", e)
+            }
+            Error::PlatformError { call, code } => {
+                write!(f, "\"{}\" failed with error code {:#010x}.", call, code)
             }
         }
     }
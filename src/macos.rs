@@ -6,57 +6,181 @@
 // with a bit of rewrite by @youngsing to make it more like cocoa::appkit style.
 
 /// <https://developer.apple.com/documentation/appkit/nsvisualeffectview/material>
-#[repr(u64)]
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
 pub enum NSVisualEffectMaterial {
     #[deprecated(
         since = "macOS 10.14",
         note = "A default material appropriate for the view's effectiveAppearance.  You should instead choose an appropriate semantic material."
     )]
-    AppearanceBased = 0,
+    AppearanceBased,
     #[deprecated(since = "macOS 10.14", note = "Use a semantic material instead.")]
-    Light = 1,
+    Light,
     #[deprecated(since = "macOS 10.14", note = "Use a semantic material instead.")]
-    Dark = 2,
+    Dark,
     #[deprecated(since = "macOS 10.14", note = "Use a semantic material instead.")]
-    MediumLight = 8,
+    MediumLight,
     #[deprecated(since = "macOS 10.14", note = "Use a semantic material instead.")]
-    UltraDark = 9,
+    UltraDark,
 
     /// macOS 10.10+
-    Titlebar = 3,
+    Titlebar,
     /// macOS 10.10+
-    Selection = 4,
+    Selection,
 
     /// macOS 10.11+
-    Menu = 5,
+    Menu,
     /// macOS 10.11+
-    Popover = 6,
+    Popover,
     /// macOS 10.11+
-    Sidebar = 7,
+    Sidebar,
 
     /// macOS 10.14+
-    HeaderView = 10,
+    HeaderView,
     /// macOS 10.14+
-    Sheet = 11,
+    Sheet,
     /// macOS 10.14+
-    WindowBackground = 12,
+    WindowBackground,
     /// macOS 10.14+
-    HudWindow = 13,
+    HudWindow,
     /// macOS 10.14+
-    FullScreenUI = 15,
+    FullScreenUI,
     /// macOS 10.14+
-    Tooltip = 17,
+    Tooltip,
     /// macOS 10.14+
-    ContentBackground = 18,
+    ContentBackground,
     /// macOS 10.14+
-    UnderWindowBackground = 21,
+    UnderWindowBackground,
     /// macOS 10.14+
-    UnderPageBackground = 22,
+    UnderPageBackground,
+
+    /// The standard translucent window background of the running macOS version,
+    /// picked from [`NSVisualEffectMaterial::AUTO_MATERIALS`].
+    Auto,
+
+    /// A raw `NSVisualEffectMaterial` value, for materials this crate doesn't know yet.
+    /// It is passed to AppKit as is, without checking whether the running macOS version has it.
+    Custom(isize),
+}
+
+impl NSVisualEffectMaterial {
+    /// The materials [`NSVisualEffectMaterial::Auto`] stands for, with the macOS version each one is used from,
+    /// newest first. Deprecated materials are only used on macOS versions where they aren't deprecated yet.
+    #[allow(deprecated)]
+    pub const AUTO_MATERIALS: [((u64, u64), NSVisualEffectMaterial); 2] = [
+        ((10, 14), NSVisualEffectMaterial::UnderWindowBackground),
+        ((10, 10), NSVisualEffectMaterial::AppearanceBased),
+    ];
+
+    /// Returns the material [`NSVisualEffectMaterial::Auto`] stands for on macOS `major.minor`,
+    /// the oldest one of [`NSVisualEffectMaterial::AUTO_MATERIALS`] for versions older than all of them.
+    pub fn auto_for_version(major: u64, minor: u64) -> Self {
+        let (_, oldest) = Self::AUTO_MATERIALS[Self::AUTO_MATERIALS.len() - 1];
+        Self::AUTO_MATERIALS
+            .iter()
+            .find(|(since, _)| (major, minor) >= *since)
+            .map_or(oldest, |(_, material)| *material)
+    }
+
+    /// Returns the material [`NSVisualEffectMaterial::Auto`] stands for on the running macOS version,
+    /// or the material itself for every other material.
+    pub fn resolve_auto(&self) -> Self {
+        match self {
+            Self::Auto => {
+                let (major, minor, _) = crate::macos_version().unwrap_or((10, 10, 0));
+                Self::auto_for_version(major, minor)
+            }
+            material => *material,
+        }
+    }
+
+    /// Returns the material for a raw `NSVisualEffectMaterial` value, [`NSVisualEffectMaterial::Custom`] for unknown ones.
+    #[allow(deprecated)]
+    pub fn from_raw(raw: isize) -> Self {
+        MATERIALS
+            .iter()
+            .find(|(_, material_raw)| *material_raw == raw)
+            .map_or(Self::Custom(raw), |(material, _)| *material)
+    }
+
+    /// Returns the raw `NSVisualEffectMaterial` value of the material,
+    /// the one of the material picked on the running macOS version for [`NSVisualEffectMaterial::Auto`].
+    pub fn to_raw(&self) -> isize {
+        match self {
+            Self::Custom(raw) => *raw,
+            Self::Auto => self.resolve_auto().to_raw(),
+            material => {
+                MATERIALS
+                    .iter()
+                    .find(|(known, _)| known == material)
+                    .expect("every material but `Auto` and `Custom` has a raw value")
+                    .1
+            }
+        }
+    }
+
+    /// Returns whether the material exists on the running macOS version,
+    /// [`apply_vibrancy`](crate::apply_vibrancy) falls back to [`NSVisualEffectMaterial::AppearanceBased`] otherwise.
+    ///
+    /// Always returns `false` on other platforms.
+    pub fn is_available(&self) -> bool {
+        #[cfg(target_os = "macos")]
+        return internal::is_material_available(*self);
+        #[cfg(not(target_os = "macos"))]
+        return false;
+    }
+
+    /// Returns the material and the appearance a deprecated material is replaced with on macOS 10.14 and newer,
+    /// see [`VibrancyOptions::keep_deprecated_materials`]. Returns `None` for materials that aren't deprecated.
+    #[allow(deprecated)]
+    pub fn modern_equivalent(&self) -> Option<(NSVisualEffectMaterial, Appearance)> {
+        match self {
+            Self::AppearanceBased => Some((Self::WindowBackground, Appearance::System)),
+            Self::Light => Some((Self::WindowBackground, Appearance::Light)),
+            Self::MediumLight => Some((Self::UnderWindowBackground, Appearance::Light)),
+            Self::Dark => Some((Self::UnderWindowBackground, Appearance::Dark)),
+            Self::UltraDark => Some((Self::HudWindow, Appearance::Dark)),
+            _ => None,
+        }
+    }
+}
+
+/// The raw values of the materials, the only place they are spelled out.
+#[allow(deprecated)]
+const MATERIALS: [(NSVisualEffectMaterial, isize); 19] = [
+    (NSVisualEffectMaterial::AppearanceBased, 0),
+    (NSVisualEffectMaterial::Light, 1),
+    (NSVisualEffectMaterial::Dark, 2),
+    (NSVisualEffectMaterial::Titlebar, 3),
+    (NSVisualEffectMaterial::Selection, 4),
+    (NSVisualEffectMaterial::Menu, 5),
+    (NSVisualEffectMaterial::Popover, 6),
+    (NSVisualEffectMaterial::Sidebar, 7),
+    (NSVisualEffectMaterial::MediumLight, 8),
+    (NSVisualEffectMaterial::UltraDark, 9),
+    (NSVisualEffectMaterial::HeaderView, 10),
+    (NSVisualEffectMaterial::Sheet, 11),
+    (NSVisualEffectMaterial::WindowBackground, 12),
+    (NSVisualEffectMaterial::HudWindow, 13),
+    (NSVisualEffectMaterial::FullScreenUI, 15),
+    (NSVisualEffectMaterial::Tooltip, 17),
+    (NSVisualEffectMaterial::ContentBackground, 18),
+    (NSVisualEffectMaterial::UnderWindowBackground, 21),
+    (NSVisualEffectMaterial::UnderPageBackground, 22),
+];
+
+/// The appearance of a window or a view.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Appearance {
+    /// Follow the appearance of the parent, which is the system appearance for windows
+    System,
+    /// `NSAppearanceNameAqua`
+    Light,
+    /// `NSAppearanceNameDarkAqua`, macOS 10.14+
+    Dark,
 }
 
 /// <https://developer.apple.com/documentation/appkit/nsvisualeffectview/state>
-#[allow(dead_code)]
 #[repr(u64)]
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum NSVisualEffectState {
@@ -68,35 +192,250 @@ pub enum NSVisualEffectState {
     Inactive = 2,
 }
 
+/// <https://developer.apple.com/documentation/appkit/nsvisualeffectview/blendingmode>
+#[repr(u64)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NSVisualEffectBlendingMode {
+    /// Blur what is behind the window, like the desktop and other windows
+    BehindWindow = 0,
+    /// Blur the content of the window behind the effect view
+    WithinWindow = 1,
+}
+
+impl Default for NSVisualEffectBlendingMode {
+    fn default() -> Self {
+        Self::BehindWindow
+    }
+}
+
+/// A rectangle of the content view in logical points, see [`VibrancyOptions::frame`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct VibrancyFrame {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    /// Whether `y` is measured from the top of the content view instead of the bottom like AppKit does.
+    pub flipped: bool,
+}
+
+/// How the effect view follows the content view when the window is resized, see [`VibrancyOptions::anchor`].
+///
+/// [`VibrancyAnchor::Fill`] keeps the view covering the whole content view, use one of the others
+/// together with [`VibrancyOptions::frame`] for a region of a fixed size.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VibrancyAnchor {
+    /// Keep the distance to every edge, growing and shrinking with the content view
+    Fill,
+    /// Stick to the left edge and keep the full height, like a sidebar
+    Left,
+    /// Stick to the right edge and keep the full height, like an inspector
+    Right,
+    /// Stick to the top edge and keep the full width, like a toolbar
+    Top,
+    /// Stick to the bottom edge and keep the full width, like a status bar
+    Bottom,
+    /// Keep the size and the distance to the top left corner
+    Fixed,
+}
+
+impl Default for VibrancyAnchor {
+    fn default() -> Self {
+        Self::Fill
+    }
+}
+
+/// Where the effect view is added among the subviews of the content view, see [`VibrancyOptions::placement`].
+///
+/// The placement only applies when the view is added, subviews added later, like the webview
+/// of a Tauri or wry window, go on top of all existing subviews. Apply vibrancy after creating them
+/// when using [`VibrancyPlacement::Above`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VibrancyPlacement {
+    /// Behind all other subviews, `NSWindowBelow`
+    Below,
+    /// In front of all other subviews, `NSWindowAbove`
+    Above,
+}
+
+impl Default for VibrancyPlacement {
+    fn default() -> Self {
+        Self::Below
+    }
+}
+
+/// Options for [`apply_vibrancy_with_options`](crate::apply_vibrancy_with_options).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct VibrancyOptions {
+    /// Whether the effect view is active, `None` follows the active state of the window.
+    pub state: Option<NSVisualEffectState>,
+    /// The corner radius of the effect view, `None` or `0.0` means square corners.
+    pub radius: Option<f64>,
+    /// What the effect view blurs.
+    ///
+    /// Unless [`VibrancyOptions::placement`] says otherwise, the effect view is added above the other subviews
+    /// of the content view with [`NSVisualEffectBlendingMode::WithinWindow`] so it has content to blur,
+    /// and below them with [`NSVisualEffectBlendingMode::BehindWindow`].
+    pub blending_mode: NSVisualEffectBlendingMode,
+    /// Where the effect view is added among the subviews of the content view,
+    /// `None` picks the placement matching [`VibrancyOptions::blending_mode`].
+    pub placement: Option<VibrancyPlacement>,
+    /// The part of the content view the effect view covers, `None` for the whole content view.
+    pub frame: Option<VibrancyFrame>,
+    /// How the effect view follows the content view when the window is resized.
+    pub anchor: VibrancyAnchor,
+    /// Use deprecated materials as they are on macOS 10.14 and newer, instead of replacing them
+    /// with the modern material and appearance returned by [`NSVisualEffectMaterial::modern_equivalent`].
+    ///
+    /// Deprecated materials don't adapt to the dark mode and look washed out on newer macOS versions.
+    pub keep_deprecated_materials: bool,
+    /// Leave out the title bar and the toolbar of windows whose content view extends below them,
+    /// so they keep their own material. Only used without a [`VibrancyOptions::frame`].
+    ///
+    /// The effect view follows the content layout rect when the toolbar is shown or hidden on macOS 10.11+,
+    /// and keeps the title bar height it had when it was applied before that.
+    pub inset_titlebar: bool,
+    /// Whether the effect view is emphasized, ignored before macOS 10.12,
+    /// see [`set_vibrancy_emphasized`](crate::set_vibrancy_emphasized).
+    pub emphasized: bool,
+    /// The intensity of the effect from `0.0` to `1.0`, which blends the effect view with what is behind it,
+    /// `None` means `1.0`. See [`set_vibrancy_alpha`](crate::set_vibrancy_alpha).
+    pub alpha: Option<f64>,
+    /// A color drawn over the effect, like the color of [`apply_acrylic`](crate::apply_acrylic) on Windows,
+    /// `None` for no tint. See [`set_vibrancy_tint`](crate::set_vibrancy_tint).
+    pub tint: Option<crate::Color>,
+    /// Don't add the effect view if the user turned on "Reduce transparency" in the accessibility settings,
+    /// where it is drawn as an opaque gray. [`Applied::SkippedReducedTransparency`](crate::Applied::SkippedReducedTransparency)
+    /// is returned then, see [`is_transparency_reduced`](crate::is_transparency_reduced).
+    pub skip_when_transparency_reduced: bool,
+    /// Make the content view and the effect view layer-backed, which fixes flickering and black flashes
+    /// where the effect view meets layer-backed content like a webview.
+    ///
+    /// Layer-backed views are drawn into their own layers and composited by Core Animation,
+    /// which costs memory for each layer and changes how views that draw themselves are redrawn.
+    /// [`clear_vibrancy`](crate::clear_vibrancy) turns layer-backing off again if this turned it on.
+    pub force_layer_backing: bool,
+}
+
+/// Insets in points, see [`VibrancyMask::Image`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct VibrancyInsets {
+    pub top: f64,
+    pub left: f64,
+    pub bottom: f64,
+    pub right: f64,
+}
+
+/// A mask for the effect view, see [`set_vibrancy_mask`](crate::set_vibrancy_mask).
+#[derive(Clone, Debug, PartialEq)]
+pub enum VibrancyMask {
+    /// An image of `width` by `height` RGBA pixels, each pixel is a point and only the alpha channel is used.
+    ///
+    /// The parts of the image within `cap_insets` from its edges keep their size
+    /// and the rest of the image is stretched to the size of the effect view.
+    Image {
+        rgba: Vec<u8>,
+        width: u32,
+        height: u32,
+        cap_insets: VibrancyInsets,
+    },
+    /// A rectangle covering the whole effect view with the given corner radii in points.
+    RoundedRect {
+        top_left: f64,
+        top_right: f64,
+        bottom_right: f64,
+        bottom_left: f64,
+    },
+}
+
+/// Keeps observing until it is dropped, see [`observe_appearance_changes`](crate::observe_appearance_changes),
+/// [`observe_transparency_changes`](crate::observe_transparency_changes) and [`propagate_to_sheets`](crate::propagate_to_sheets).
+pub struct ObserverHandle {
+    #[cfg(target_os = "macos")]
+    observer: cocoa::base::id,
+    #[cfg(target_os = "macos")]
+    remove: fn(cocoa::base::id),
+}
+
+#[cfg(target_os = "macos")]
+impl Drop for ObserverHandle {
+    fn drop(&mut self) {
+        (self.remove)(self.observer);
+    }
+}
+
 #[cfg(target_os = "macos")]
-pub use internal::apply_vibrancy;
+pub use internal::{
+    apply_titlebar_vibrancy, apply_vibrancy, apply_vibrancy_on_main, apply_vibrancy_region,
+    clear_vibrancy, clear_vibrancy_region, ensure_vibrancy_behind_content, get_vibrancy,
+    get_vibrancy_view, is_transparency_reduced, macos_version, make_titlebar_transparent,
+    ns_window, observe_appearance_changes, observe_transparency_changes,
+    prepare_transparent_window, propagate_to_sheets, restore_titlebar, set_content_allows_vibrancy,
+    set_vibrancy_alpha, set_vibrancy_animated, set_vibrancy_emphasized, set_vibrancy_mask,
+    set_vibrancy_tint, set_window_appearance, set_window_shadow,
+};
 
 #[cfg(target_os = "macos")]
 mod internal {
-    use super::{NSVisualEffectMaterial, NSVisualEffectState};
+    use std::{
+        ffi::{c_void, CStr},
+        panic::{catch_unwind, AssertUnwindSafe},
+        ptr::NonNull,
+        sync::mpsc::{self, Sender},
+        time::Duration,
+    };
+
+    use super::{
+        Appearance, NSVisualEffectBlendingMode, NSVisualEffectMaterial, NSVisualEffectState,
+        ObserverHandle, VibrancyAnchor, VibrancyFrame, VibrancyInsets, VibrancyMask,
+        VibrancyOptions, VibrancyPlacement,
+    };
 
     use cocoa::{
         appkit::{
-            NSAppKitVersionNumber, NSAppKitVersionNumber10_10, NSAppKitVersionNumber10_11,
-            NSAutoresizingMaskOptions, NSView, NSViewHeightSizable, NSViewWidthSizable, NSWindow,
-            NSWindowOrderingMode,
+            NSAutoresizingMaskOptions, NSView, NSViewHeightSizable, NSViewMaxXMargin,
+            NSViewMaxYMargin, NSViewMinXMargin, NSViewMinYMargin, NSViewWidthSizable, NSWindow,
+            NSWindowOrderingMode, NSWindowStyleMask, NSWindowTitleVisibility,
         },
-        base::{id, nil, BOOL},
-        foundation::{NSAutoreleasePool, NSPoint, NSRect, NSSize},
+        base::{id, nil, BOOL, NO, YES},
+        foundation::{NSAutoreleasePool, NSInteger, NSPoint, NSRect, NSSize, NSString, NSUInteger},
+    };
+    use objc::{
+        class,
+        declare::ClassDecl,
+        msg_send,
+        runtime::{object_getClass, Class, Object, Sel},
+        sel, sel_impl,
     };
-    use objc::{class, msg_send, sel, sel_impl};
 
-    use crate::Error;
+    use crate::{Applied, Error};
 
-    #[allow(deprecated)]
     pub fn apply_vibrancy(
         window: id,
         appearance: NSVisualEffectMaterial,
-        state: Option<NSVisualEffectState>,
-        radius: Option<f64>,
-    ) -> Result<(), Error> {
+        options: VibrancyOptions,
+    ) -> Result<Applied, Error> {
+        apply_tagged_vibrancy(window, EFFECT_VIEW_IDENTIFIER, appearance, options)
+    }
+
+    pub fn apply_vibrancy_region(
+        window: id,
+        region: &str,
+        appearance: NSVisualEffectMaterial,
+        options: VibrancyOptions,
+    ) -> Result<Applied, Error> {
+        apply_tagged_vibrancy(window, &region_identifier(region), appearance, options)
+    }
+
+    #[allow(deprecated)]
+    fn apply_tagged_vibrancy(
+        window: id,
+        identifier: &str,
+        appearance: NSVisualEffectMaterial,
+        options: VibrancyOptions,
+    ) -> Result<Applied, Error> {
         unsafe {
-            if NSAppKitVersionNumber < NSAppKitVersionNumber10_10 {
+            if !is_at_least(10, 10, 0) {
                 eprintln!("\"NSVisualEffectView\" is only available on macOS 10.10 or newer");
                 return Err(Error::UnsupportedPlatformVersion(
                     "\"apply_vibrancy()\" is only available on macOS 10.0 or newer.",
@@ -109,138 +448,2183 @@ mod internal {
                 ));
             }
 
-            let mut m = appearance;
-            if appearance as u32 > 9 && NSAppKitVersionNumber < NSAppKitVersionNumber10_14 {
-                m = NSVisualEffectMaterial::AppearanceBased;
-            } else if appearance as u32 > 4 && NSAppKitVersionNumber < NSAppKitVersionNumber10_11 {
-                m = NSVisualEffectMaterial::AppearanceBased;
+            // the menu bar draws the status item windows itself, what it draws would cover the effect view
+            if let Some(status_bar_window) = Class::get("NSStatusBarWindow") {
+                let is_status_bar_window: BOOL =
+                    msg_send![window, isKindOfClass: status_bar_window];
+                if is_status_bar_window == YES {
+                    return Err(Error::InvalidArgument(
+                        "\"apply_vibrancy()\" can't be used on the window of a status item, which belongs to the menu bar. Apply it to the window shown below the status item instead.",
+                    ));
+                }
+            }
+
+            if options.skip_when_transparency_reduced && is_transparency_reduced() {
+                return Ok(Applied::SkippedReducedTransparency);
             }
 
-            let ns_view: id = window.contentView();
+            let ns_view = require_content_view(
+                window,
+                window.contentView(),
+                "\"apply_vibrancy()\" requires a window.",
+                "\"apply_vibrancy()\" requires the window to have a content view.",
+            )?;
+
+            let alpha = clamp_alpha(
+                options.alpha.unwrap_or(1.0),
+                "\"apply_vibrancy()\" requires the alpha to be a number.",
+            )?;
+            let (m, view_appearance) =
+                resolve_material(appearance, options.keep_deprecated_materials);
+
             let bounds = NSView::bounds(ns_view);
+            let view_flipped: BOOL = msg_send![ns_view, isFlipped];
+            let view_flipped = view_flipped == YES;
+            let layout_rect: Option<NSRect> = if options.inset_titlebar {
+                let layout_rect: NSRect = msg_send![window, contentLayoutRect];
+                Some(msg_send![ns_view, convertRect: layout_rect fromView: nil])
+            } else {
+                None
+            };
+            let frame = effect_view_frame(bounds, view_flipped, options.frame, layout_rect);
+            let (container, relative_to) = effect_container(window);
+            let frame: NSRect = if container == ns_view {
+                frame
+            } else {
+                msg_send![ns_view, convertRect: frame toView: container]
+            };
+            let container_flipped: BOOL = msg_send![container, isFlipped];
 
-            let blurred_view =
-                NSVisualEffectView::initWithFrame_(NSVisualEffectView::alloc(nil), bounds);
-            blurred_view.autorelease();
+            // update the view added by a previous call instead of stacking another one on top of it
+            let blurred_view = find_tagged_view(window, identifier);
+            let blurred_view = if blurred_view != nil {
+                // constraints of a previous call go away with the superview, the view is added back below
+                let constrained: BOOL =
+                    msg_send![blurred_view, translatesAutoresizingMaskIntoConstraints];
+                if constrained == NO {
+                    let _: id = msg_send![blurred_view, retain];
+                    blurred_view.autorelease();
+                    NSVisualEffectView::removeFromSuperview(blurred_view);
+                }
+                let _: () = msg_send![blurred_view, setFrame: frame];
+                blurred_view
+            } else {
+                let blurred_view =
+                    NSVisualEffectView::initWithFrame_(NSVisualEffectView::alloc(nil), frame);
+                blurred_view.autorelease();
+                // lets `clear_vibrancy()` tell our view apart from the app's own effect views
+                let _: () = msg_send![blurred_view, setIdentifier: ns_string(identifier)];
+                blurred_view
+            };
 
             blurred_view.setMaterial_(m);
-            blurred_view.setCornerRadius_(radius.unwrap_or(0.0));
-            blurred_view.setBlendingMode_(NSVisualEffectBlendingMode::BehindWindow);
-            blurred_view.setState_(state.unwrap_or(NSVisualEffectState::FollowsWindowActiveState));
+            // also resets the appearance a previous call gave to a reused view
+            let _: () = msg_send![blurred_view, setAppearance: ns_appearance(view_appearance)];
+            match options.radius {
+                Some(radius) if radius > 0.0 => {
+                    // the layer keeps the radius when the view is resized
+                    let _: () = msg_send![blurred_view, setWantsLayer: YES];
+                    let layer: id = msg_send![blurred_view, layer];
+                    let _: () = msg_send![layer, setCornerRadius: radius];
+                    let _: () = msg_send![layer, setMasksToBounds: YES];
+                }
+                _ => {
+                    // a previous call may have rounded the corners
+                    let layer: id = msg_send![blurred_view, layer];
+                    if layer != nil {
+                        let _: () = msg_send![layer, setCornerRadius: 0.0f64];
+                        let _: () = msg_send![layer, setMasksToBounds: NO];
+                    }
+                }
+            }
+            blurred_view.setBlendingMode_(options.blending_mode);
+            let _: () = msg_send![blurred_view, setAlphaValue: alpha];
+            set_tint(blurred_view, options.tint);
+            if is_at_least(10, 12, 0) {
+                blurred_view.setEmphasized_(if options.emphasized { YES } else { NO });
+            }
+            blurred_view.setState_(options.state.unwrap_or_else(|| default_state(window)));
             NSVisualEffectView::setAutoresizingMask_(
                 blurred_view,
-                NSViewWidthSizable | NSViewHeightSizable,
+                autoresizing_mask(options.anchor, container_flipped == YES),
             );
+            // the mask is ignored if the app moved the view to auto layout
+            let _: () = msg_send![blurred_view, setTranslatesAutoresizingMaskIntoConstraints: YES];
+            // the mask only has an effect if the container resizes its subviews
+            let _: () = msg_send![container, setAutoresizesSubviews: YES];
+            // within-window blending blurs what is below the view, so it must be on top of the content
+            let placement = options.placement.unwrap_or(match options.blending_mode {
+                NSVisualEffectBlendingMode::BehindWindow => VibrancyPlacement::Below,
+                NSVisualEffectBlendingMode::WithinWindow => VibrancyPlacement::Above,
+            });
+            let position = match placement {
+                VibrancyPlacement::Below => NSWindowOrderingMode::NSWindowBelow,
+                VibrancyPlacement::Above => NSWindowOrderingMode::NSWindowAbove,
+            };
+            // the container must be layer-backed before the effect view is added to it, or the view flashes
+            if options.force_layer_backing {
+                force_layer_backing(container);
+                let _: () = msg_send![blurred_view, setWantsLayer: YES];
+            }
+            // this also moves a reused view, in case the blending mode changed
+            let _: () = msg_send![container, addSubview: blurred_view positioned: position relativeTo: relative_to];
 
-            let _: () = msg_send![ns_view, addSubview: blurred_view positioned: NSWindowOrderingMode::NSWindowBelow relativeTo: 0];
+            // the content layout guide moves when the toolbar is shown or hidden, unlike the autoresizing mask
+            if options.inset_titlebar && options.frame.is_none() && is_at_least(10, 11, 0) {
+                let guide: id = msg_send![window, contentLayoutGuide];
+                pin_view(
+                    blurred_view,
+                    (guide, NSLayoutAttributeTop),
+                    (container, NSLayoutAttributeBottom),
+                );
+            }
+
+            // the shadow was computed from the window before it became translucent
+            let _: () = msg_send![window, invalidateShadow];
+
+            remember_effect_view(
+                window,
+                blurred_view,
+                position.bits(),
+                options.frame.is_none() && !options.inset_titlebar,
+            );
         }
-        Ok(())
+        Ok(Applied::VisualEffectView)
     }
 
-    #[allow(non_upper_case_globals)]
-    const NSAppKitVersionNumber10_14: f64 = 1671.0;
+    /// Returns `content_view`, the content view of `window`, or the error for the one that is `nil`.
+    ///
+    /// Messages to `nil` return `nil`, so the content view of a `nil` window is `nil` as well.
+    fn require_content_view(
+        window: id,
+        content_view: id,
+        no_window: &'static str,
+        no_content_view: &'static str,
+    ) -> Result<id, Error> {
+        if window == nil {
+            return Err(Error::InvalidWindowHandle(no_window));
+        }
+        if content_view == nil {
+            return Err(Error::NoWindowContentView(no_content_view));
+        }
+        Ok(content_view)
+    }
 
-    // https://developer.apple.com/documentation/appkit/nsvisualeffectview/blendingmode
-    #[allow(dead_code)]
-    #[repr(u64)]
-    #[derive(Clone, Copy, Debug, PartialEq)]
-    enum NSVisualEffectBlendingMode {
-        BehindWindow = 0,
-        WithinWindow = 1,
+    /// Returns the frame of the effect view in the content view, from the `bounds` of the content view,
+    /// whether it is `flipped`, the requested `frame` and the content layout rect if the title bar is left out.
+    ///
+    /// Only the content view matters, not the style mask of the window: the content view of a borderless window
+    /// or of a full size content view covers the whole window, the one of a titled window ends below the title bar,
+    /// and `bounds` already reflects that.
+    fn effect_view_frame(
+        bounds: NSRect,
+        flipped: bool,
+        frame: Option<VibrancyFrame>,
+        layout_rect: Option<NSRect>,
+    ) -> NSRect {
+        match (frame, layout_rect) {
+            (Some(frame), _) => {
+                let y = if frame.flipped == flipped {
+                    frame.y
+                } else {
+                    bounds.size.height - frame.y - frame.height
+                };
+                // the bounds of a scrolled or otherwise offset content view don't start at zero
+                NSRect::new(
+                    NSPoint::new(bounds.origin.x + frame.x, bounds.origin.y + y),
+                    NSSize::new(frame.width, frame.height),
+                )
+            }
+            (None, Some(layout_rect)) => layout_rect,
+            (None, None) => bounds,
+        }
     }
 
-    // macos 10.10+
-    // https://developer.apple.com/documentation/appkit/nsvisualeffectview
-    #[allow(non_snake_case)]
-    trait NSVisualEffectView: Sized {
-        unsafe fn alloc(_: Self) -> id {
-            msg_send![class!(NSVisualEffectView), alloc]
+    /// Returns the material and the appearance of the effect view for `material` on the running macOS version.
+    #[allow(deprecated)]
+    unsafe fn resolve_material(
+        material: NSVisualEffectMaterial,
+        keep_deprecated_materials: bool,
+    ) -> (NSVisualEffectMaterial, Appearance) {
+        let material = material.resolve_auto();
+        match material.modern_equivalent() {
+            Some((modern, view_appearance))
+                if !keep_deprecated_materials && is_at_least(10, 14, 0) =>
+            {
+                (modern, view_appearance)
+            }
+            _ if is_material_available(material) => (material, Appearance::System),
+            _ => (NSVisualEffectMaterial::AppearanceBased, Appearance::System),
         }
+    }
 
-        unsafe fn init(self) -> id;
-        unsafe fn initWithFrame_(self, frameRect: NSRect) -> id;
-        unsafe fn bounds(self) -> NSRect;
-        unsafe fn frame(self) -> NSRect;
-        unsafe fn setFrameSize(self, frameSize: NSSize);
-        unsafe fn setFrameOrigin(self, frameOrigin: NSPoint);
+    pub fn clear_vibrancy(window: id) -> Result<bool, Error> {
+        unsafe {
+            if !msg_send![class!(NSThread), isMainThread] {
+                return Err(Error::NotMainThread(
+                    "\"clear_vibrancy()\" can only be used on the main thread.",
+                ));
+            }
 
-        unsafe fn superview(self) -> id;
-        unsafe fn removeFromSuperview(self);
-        unsafe fn setAutoresizingMask_(self, autoresizingMask: NSAutoresizingMaskOptions);
+            // collected first, removing a view while iterating would skip the next one
+            let views = tagged_views(window);
+            for &view in &views {
+                forget_effect_view(window, view);
+                NSVisualEffectView::removeFromSuperview(view);
+            }
+            restore_window_background(window);
+            let (container, _) = effect_container(window);
+            restore_layer_backing(container);
+            Ok(!views.is_empty())
+        }
+    }
 
-        // API_AVAILABLE(macos(10.12));
-        unsafe fn isEmphasized(self) -> BOOL;
-        // API_AVAILABLE(macos(10.12));
-        unsafe fn setEmphasized_(self, emphasized: BOOL);
+    pub fn clear_vibrancy_region(window: id, region: &str) -> Result<bool, Error> {
+        unsafe {
+            if !msg_send![class!(NSThread), isMainThread] {
+                return Err(Error::NotMainThread(
+                    "\"clear_vibrancy_region()\" can only be used on the main thread.",
+                ));
+            }
 
-        unsafe fn setMaterial_(self, material: NSVisualEffectMaterial);
-        unsafe fn setCornerRadius_(self, radius: f64);
-        unsafe fn setState_(self, state: NSVisualEffectState);
-        unsafe fn setBlendingMode_(self, mode: NSVisualEffectBlendingMode);
+            let blurred_view = find_tagged_view(window, &region_identifier(region));
+            if blurred_view == nil {
+                return Ok(false);
+            }
+            forget_effect_view(window, blurred_view);
+            NSVisualEffectView::removeFromSuperview(blurred_view);
+        }
+        Ok(true)
     }
 
-    #[allow(non_snake_case)]
-    impl NSVisualEffectView for id {
-        unsafe fn init(self) -> id {
-            msg_send![self, init]
+    pub fn set_vibrancy_emphasized(window: id, emphasized: bool) -> Result<(), Error> {
+        unsafe {
+            if !is_at_least(10, 12, 0) {
+                return Err(Error::UnsupportedPlatformVersion(
+                    "\"set_vibrancy_emphasized()\" is only available on macOS 10.12 or newer.",
+                ));
+            }
+
+            if !msg_send![class!(NSThread), isMainThread] {
+                return Err(Error::NotMainThread(
+                    "\"set_vibrancy_emphasized()\" can only be used on the main thread.",
+                ));
+            }
+
+            let blurred_view = require_effect_view(
+                find_effect_view(window),
+                "\"set_vibrancy_emphasized()\" requires vibrancy to be applied to the window with \"apply_vibrancy()\" first.",
+            )?;
+            blurred_view.setEmphasized_(if emphasized { YES } else { NO });
         }
+        Ok(())
+    }
 
-        unsafe fn initWithFrame_(self, frameRect: NSRect) -> id {
-            msg_send![self, initWithFrame: frameRect]
+    pub fn set_vibrancy_mask(window: id, mask: Option<&VibrancyMask>) -> Result<(), Error> {
+        unsafe {
+            if !msg_send![class!(NSThread), isMainThread] {
+                return Err(Error::NotMainThread(
+                    "\"set_vibrancy_mask()\" can only be used on the main thread.",
+                ));
+            }
+
+            let blurred_view = require_effect_view(
+                find_effect_view(window),
+                "\"set_vibrancy_mask()\" requires vibrancy to be applied to the window with \"apply_vibrancy()\" first.",
+            )?;
+
+            let image = match mask {
+                Some(VibrancyMask::Image {
+                    rgba,
+                    width,
+                    height,
+                    cap_insets,
+                }) => {
+                    if rgba.len() != *width as usize * *height as usize * 4 {
+                        return Err(Error::InvalidArgument(
+                            "\"set_vibrancy_mask()\" requires the image to have \"width * height * 4\" bytes.",
+                        ));
+                    }
+                    mask_image(rgba, *width, *height, 1.0, *cap_insets)
+                }
+                Some(&VibrancyMask::RoundedRect {
+                    top_left,
+                    top_right,
+                    bottom_right,
+                    bottom_left,
+                }) => {
+                    // draw at twice the size so the corners are smooth on Retina displays
+                    let scale = 2.0;
+                    let cap_insets = VibrancyInsets {
+                        top: top_left.max(top_right).max(0.0).ceil(),
+                        left: top_left.max(bottom_left).max(0.0).ceil(),
+                        bottom: bottom_left.max(bottom_right).max(0.0).ceil(),
+                        right: top_right.max(bottom_right).max(0.0).ceil(),
+                    };
+                    // one stretchable point between the caps
+                    let width = ((cap_insets.left + cap_insets.right + 1.0) * scale) as u32;
+                    let height = ((cap_insets.top + cap_insets.bottom + 1.0) * scale) as u32;
+                    let rgba = rounded_rect_pixels(
+                        width,
+                        height,
+                        scale,
+                        [top_left, top_right, bottom_right, bottom_left],
+                    );
+                    mask_image(&rgba, width, height, scale, cap_insets)
+                }
+                None => nil,
+            };
+            let _: () = msg_send![blurred_view, setMaskImage: image];
         }
+        Ok(())
+    }
 
-        unsafe fn bounds(self) -> NSRect {
-            msg_send![self, bounds]
+    #[repr(C)]
+    struct NSEdgeInsets {
+        top: f64,
+        left: f64,
+        bottom: f64,
+        right: f64,
+    }
+
+    #[allow(non_upper_case_globals)]
+    const NSImageResizingModeStretch: i64 = 1;
+
+    /// Creates an autoreleased `NSImage` stretched around `cap_insets`, given in points,
+    /// out of RGBA pixels drawn at `scale` pixels per point.
+    unsafe fn mask_image(
+        rgba: &[u8],
+        width: u32,
+        height: u32,
+        scale: f64,
+        cap_insets: VibrancyInsets,
+    ) -> id {
+        let color_space = NSString::alloc(nil)
+            .init_str("NSDeviceRGBColorSpace")
+            .autorelease();
+        let rep: id = msg_send![class!(NSBitmapImageRep), alloc];
+        let rep: id = msg_send![rep,
+            initWithBitmapDataPlanes: std::ptr::null_mut::<*mut u8>()
+            pixelsWide: width as i64
+            pixelsHigh: height as i64
+            bitsPerSample: 8i64
+            samplesPerPixel: 4i64
+            hasAlpha: YES
+            isPlanar: NO
+            colorSpaceName: color_space
+            bytesPerRow: width as i64 * 4
+            bitsPerPixel: 32i64
+        ];
+        if rep == nil {
+            return nil;
+        }
+        let rep = rep.autorelease();
+        // the bitmap uses premultiplied alpha
+        let data: *mut u8 = msg_send![rep, bitmapData];
+        for (i, pixel) in rgba.chunks_exact(4).enumerate() {
+            let alpha = pixel[3] as u32;
+            for (c, &value) in pixel[..3].iter().enumerate() {
+                *data.add(i * 4 + c) = (value as u32 * alpha / 255) as u8;
+            }
+            *data.add(i * 4 + 3) = pixel[3];
         }
 
-        unsafe fn frame(self) -> NSRect {
-            msg_send![self, frame]
+        let size = NSSize::new(width as f64 / scale, height as f64 / scale);
+        let _: () = msg_send![rep, setSize: size];
+        let image: id = msg_send![class!(NSImage), alloc];
+        let image: id = msg_send![image, initWithSize: size];
+        let image = image.autorelease();
+        let _: () = msg_send![image, addRepresentation: rep];
+        let insets = NSEdgeInsets {
+            top: cap_insets.top,
+            left: cap_insets.left,
+            bottom: cap_insets.bottom,
+            right: cap_insets.right,
+        };
+        let _: () = msg_send![image, setCapInsets: insets];
+        let _: () = msg_send![image, setResizingMode: NSImageResizingModeStretch];
+        image
+    }
+
+    /// Draws a white rounded rectangle filling `width` by `height` pixels, with antialiased corners.
+    /// `radii` are in points, clockwise from the top left corner.
+    fn rounded_rect_pixels(width: u32, height: u32, scale: f64, radii: [f64; 4]) -> Vec<u8> {
+        let (w, h) = (width as f64, height as f64);
+        let mut rgba = Vec::with_capacity(width as usize * height as usize * 4);
+        // rows go from the top to the bottom of the image
+        for y in 0..height {
+            for x in 0..width {
+                let (px, py) = (x as f64 + 0.5, y as f64 + 0.5);
+                let coverage = radii
+                    .iter()
+                    .enumerate()
+                    .map(|(corner, &radius)| {
+                        let r = radius.max(0.0) * scale;
+                        let (cx, cy) = match corner {
+                            0 => (r, r),
+                            1 => (w - r, r),
+                            2 => (w - r, h - r),
+                            _ => (r, h - r),
+                        };
+                        let inside_corner = match corner {
+                            0 => px < cx && py < cy,
+                            1 => px > cx && py < cy,
+                            2 => px > cx && py > cy,
+                            _ => px < cx && py > cy,
+                        };
+                        if !inside_corner {
+                            return 1.0;
+                        }
+                        let distance = ((px - cx).powi(2) + (py - cy).powi(2)).sqrt();
+                        (r - distance + 0.5).clamp(0.0, 1.0)
+                    })
+                    .fold(1.0f64, f64::min);
+                rgba.extend_from_slice(&[255, 255, 255, (coverage * 255.0).round() as u8]);
+            }
         }
+        rgba
+    }
 
-        unsafe fn setFrameSize(self, frameSize: NSSize) {
-            msg_send![self, setFrameSize: frameSize]
+    /// Returns the window of an AppKit handle, some windowing libraries only fill in the view.
+    pub fn ns_window(handle: raw_window_handle::AppKitWindowHandle) -> Result<id, Error> {
+        if !handle.ns_window.is_null() {
+            return Ok(handle.ns_window as id);
         }
+        if handle.ns_view.is_null() {
+            return Err(Error::InvalidWindowHandle(
+                "The AppKit window handle has neither a window nor a view.",
+            ));
+        }
+        let window: id = unsafe { msg_send![handle.ns_view as id, window] };
+        if window == nil {
+            return Err(Error::InvalidWindowHandle(
+                "The view of the AppKit window handle is not in a window.",
+            ));
+        }
+        Ok(window)
+    }
 
-        unsafe fn setFrameOrigin(self, frameOrigin: NSPoint) {
-            msg_send![self, setFrameOrigin: frameOrigin]
+    pub fn apply_vibrancy_on_main(
+        window: id,
+        appearance: NSVisualEffectMaterial,
+        options: VibrancyOptions,
+    ) -> Result<Applied, Error> {
+        run_on_main(|| apply_vibrancy(window, appearance, options))
+    }
+
+    #[repr(C)]
+    struct dispatch_queue_s {
+        _private: [u8; 0],
+    }
+
+    #[link(name = "System", kind = "dylib")]
+    extern "C" {
+        static _dispatch_main_q: dispatch_queue_s;
+        fn dispatch_sync_f(
+            queue: *const dispatch_queue_s,
+            context: *mut c_void,
+            work: extern "C" fn(*mut c_void),
+        );
+    }
+
+    /// Runs `f` on the main thread and waits for it, or runs it right away if this is the main thread,
+    /// since dispatching synchronously onto the current queue would deadlock.
+    fn run_on_main<R>(f: impl FnOnce() -> R) -> R {
+        let is_main_thread: BOOL = unsafe { msg_send![class!(NSThread), isMainThread] };
+        if is_main_thread == YES {
+            return f();
         }
 
-        unsafe fn superview(self) -> id {
-            msg_send![self, superview]
+        extern "C" fn work(context: *mut c_void) {
+            let call = unsafe { &mut *(context as *mut &mut dyn FnMut()) };
+            call();
         }
 
-        unsafe fn removeFromSuperview(self) {
-            msg_send![self, removeFromSuperview]
+        let mut f = Some(f);
+        let mut result = None;
+        {
+            let mut call = || {
+                if let Some(f) = f.take() {
+                    result = Some(f());
+                }
+            };
+            let mut call: &mut dyn FnMut() = &mut call;
+            unsafe {
+                dispatch_sync_f(
+                    &_dispatch_main_q,
+                    &mut call as *mut &mut dyn FnMut() as *mut c_void,
+                    work,
+                )
+            };
         }
+        result.expect("the main queue didn't run the call")
+    }
 
-        unsafe fn setAutoresizingMask_(self, autoresizingMask: NSAutoresizingMaskOptions) {
-            msg_send![self, setAutoresizingMask: autoresizingMask]
+    pub fn is_material_available(material: NSVisualEffectMaterial) -> bool {
+        let (major, minor) = material_macos_version(material);
+        is_at_least(major, minor, 0)
+    }
+
+    /// The AppKit version each material was added in.
+    #[allow(deprecated)]
+    fn material_macos_version(material: NSVisualEffectMaterial) -> (u64, u64) {
+        use NSVisualEffectMaterial::*;
+        match material {
+            AppearanceBased | Light | Dark | Titlebar | Selection => (10, 10),
+            MediumLight | UltraDark | Menu | Popover | Sidebar => (10, 11),
+            HeaderView
+            | Sheet
+            | WindowBackground
+            | HudWindow
+            | FullScreenUI
+            | Tooltip
+            | ContentBackground
+            | UnderWindowBackground
+            | UnderPageBackground => (10, 14),
+            // picks a material of the running version
+            Auto => (10, 10),
+            // unknown to this crate, so AppKit has to deal with it
+            Custom(_) => (10, 10),
         }
+    }
 
-        // API_AVAILABLE(macos(10.12));
-        unsafe fn isEmphasized(self) -> BOOL {
-            msg_send![self, isEmphasized]
+    pub fn set_window_appearance(window: id, appearance: Appearance) -> Result<(), Error> {
+        unsafe {
+            if appearance == Appearance::Dark && !is_at_least(10, 14, 0) {
+                return Err(Error::UnsupportedPlatformVersion(
+                    "\"set_window_appearance()\" with \"Appearance::Dark\" is only available on macOS 10.14 or newer.",
+                ));
+            }
+
+            if !msg_send![class!(NSThread), isMainThread] {
+                return Err(Error::NotMainThread(
+                    "\"set_window_appearance()\" can only be used on the main thread.",
+                ));
+            }
+
+            let _: () = msg_send![window, setAppearance: ns_appearance(appearance)];
         }
+        Ok(())
+    }
 
-        // API_AVAILABLE(macos(10.12));
-        unsafe fn setEmphasized_(self, emphasized: BOOL) {
-            msg_send![self, setEmphasized: emphasized]
+    pub fn get_vibrancy(window: id) -> Result<Option<NSVisualEffectMaterial>, Error> {
+        unsafe {
+            if !msg_send![class!(NSThread), isMainThread] {
+                return Err(Error::NotMainThread(
+                    "\"get_vibrancy()\" can only be used on the main thread.",
+                ));
+            }
+
+            // only the view tagged with our identifier is considered,
+            // effect views added by the app itself are left alone
+            let view = find_effect_view(window);
+            if view == nil {
+                return Ok(None);
+            }
+
+            let material: isize = msg_send![view, material];
+            Ok(Some(NSVisualEffectMaterial::from_raw(material)))
         }
+    }
 
-        unsafe fn setMaterial_(self, material: NSVisualEffectMaterial) {
-            msg_send![self, setMaterial: material]
+    pub fn set_content_allows_vibrancy(window: id, allows_vibrancy: bool) -> Result<(), Error> {
+        unsafe {
+            if !msg_send![class!(NSThread), isMainThread] {
+                return Err(Error::NotMainThread(
+                    "\"set_content_allows_vibrancy()\" can only be used on the main thread.",
+                ));
+            }
+
+            let ns_view = require_content_view(
+                window,
+                window.contentView(),
+                "\"set_content_allows_vibrancy()\" requires a window.",
+                "\"set_content_allows_vibrancy()\" requires the window to have a content view.",
+            )?;
+            let class = &*object_getClass(ns_view);
+            let replaced = class.name().starts_with(ALLOWS_VIBRANCY_CLASS_PREFIX);
+            if allows_vibrancy == replaced {
+                return Ok(());
+            }
+
+            if allows_vibrancy {
+                // key-value observing and other libraries replace the class of a view at runtime as well,
+                // subclassing theirs would break them when they restore the class
+                let declared_class: *const Class = msg_send![ns_view, class];
+                if !std::ptr::eq(declared_class, class) {
+                    return Err(Error::InvalidArgument(
+                        "\"set_content_allows_vibrancy()\" can't be used while the class of the content view is replaced at runtime, like when it is observed with key-value observing.",
+                    ));
+                }
+                object_setClass(ns_view, allows_vibrancy_class(class));
+            } else if let Some(superclass) = class.superclass() {
+                object_setClass(ns_view, superclass);
+            }
         }
+        Ok(())
+    }
+
+    const OBJC_ASSOCIATION_RETAIN_NONATOMIC: usize = 1;
 
-        unsafe fn setCornerRadius_(self, radius: f64) {
-            msg_send![self, setCornerRadius: radius]
+    const ALLOWS_VIBRANCY_CLASS_PREFIX: &str = "WindowVibrancyAllowsVibrancy_";
+
+    #[link(name = "objc", kind = "dylib")]
+    extern "C" {
+        fn object_setClass(object: id, class: *const Class) -> *const Class;
+        fn objc_getAssociatedObject(object: id, key: *const c_void) -> id;
+        fn objc_setAssociatedObject(object: id, key: *const c_void, value: id, policy: usize);
+    }
+
+    /// Returns a subclass of `superclass` whose `allowsVibrancy` returns `YES`, declaring it on first use.
+    /// Only the class of the view changes, so its frame, constraints and subviews are left as they are.
+    fn allows_vibrancy_class(superclass: &Class) -> &'static Class {
+        extern "C" fn allows_vibrancy(_: &Object, _: Sel) -> BOOL {
+            YES
         }
 
-        unsafe fn setState_(self, state: NSVisualEffectState) {
-            msg_send![self, setState: state]
+        let name = format!("{}{}", ALLOWS_VIBRANCY_CLASS_PREFIX, superclass.name());
+        get_or_declare_class(&name, superclass, |decl| unsafe {
+            decl.add_method(
+                sel!(allowsVibrancy),
+                allows_vibrancy as extern "C" fn(&Object, Sel) -> BOOL,
+            );
+        })
+    }
+
+    /// Returns the class named `name`, declaring it as a subclass of `superclass` with `declare` on first use.
+    ///
+    /// Another thread, another copy of this crate or the app itself may have declared the class already,
+    /// it is used as is then.
+    fn get_or_declare_class(
+        name: &str,
+        superclass: &Class,
+        declare: impl FnOnce(&mut ClassDecl),
+    ) -> &'static Class {
+        loop {
+            if let Some(class) = Class::get(name) {
+                return class;
+            }
+            if let Some(mut decl) = ClassDecl::new(name, superclass) {
+                declare(&mut decl);
+                return decl.register();
+            }
+            // the class is being declared on another thread, which registers it right after
+            std::thread::yield_now();
         }
+    }
 
-        unsafe fn setBlendingMode_(self, mode: NSVisualEffectBlendingMode) {
+    pub fn make_titlebar_transparent(window: id, hide_title: bool) -> Result<(), Error> {
+        unsafe {
+            if !is_at_least(10, 10, 0) {
+                return Err(Error::UnsupportedPlatformVersion(
+                    "\"make_titlebar_transparent()\" is only available on macOS 10.10 or newer.",
+                ));
+            }
+
+            if !msg_send![class!(NSThread), isMainThread] {
+                return Err(Error::NotMainThread(
+                    "\"make_titlebar_transparent()\" can only be used on the main thread.",
+                ));
+            }
+
+            // the content view, and an effect view filling it, grows to cover the title bar
+            window.setStyleMask_(
+                window.styleMask() | NSWindowStyleMask::NSFullSizeContentViewWindowMask,
+            );
+            window.setTitlebarAppearsTransparent_(YES);
+            if hide_title {
+                window.setTitleVisibility_(NSWindowTitleVisibility::NSWindowTitleHidden);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn restore_titlebar(window: id) -> Result<(), Error> {
+        unsafe {
+            if !is_at_least(10, 10, 0) {
+                return Err(Error::UnsupportedPlatformVersion(
+                    "\"restore_titlebar()\" is only available on macOS 10.10 or newer.",
+                ));
+            }
+
+            if !msg_send![class!(NSThread), isMainThread] {
+                return Err(Error::NotMainThread(
+                    "\"restore_titlebar()\" can only be used on the main thread.",
+                ));
+            }
+
+            window.setStyleMask_(
+                window.styleMask() & !NSWindowStyleMask::NSFullSizeContentViewWindowMask,
+            );
+            window.setTitlebarAppearsTransparent_(NO);
+            window.setTitleVisibility_(NSWindowTitleVisibility::NSWindowTitleVisible);
+        }
+        Ok(())
+    }
+
+    pub fn set_vibrancy_animated(
+        window: id,
+        material: NSVisualEffectMaterial,
+        duration: Duration,
+    ) -> Result<(), Error> {
+        run_on_main(|| unsafe {
+            let view = require_effect_view(
+                find_effect_view(window),
+                "\"set_vibrancy_animated()\" requires vibrancy to be applied to the window with \"apply_vibrancy()\" first.",
+            )?;
+
+            let (m, view_appearance) = resolve_material(material, false);
+            let animate = !duration.is_zero() && !reduce_motion();
+            if animate {
+                fade_out_snapshot(view, duration.as_secs_f64());
+            }
+            view.setMaterial_(m);
+            let _: () = msg_send![view, setAppearance: ns_appearance(view_appearance)];
+            Ok(())
+        })
+    }
+
+    /// Whether the user asked for less motion in the accessibility settings, macOS 10.12+.
+    unsafe fn reduce_motion() -> bool {
+        if !is_at_least(10, 12, 0) {
+            return false;
+        }
+        let workspace: id = msg_send![class!(NSWorkspace), sharedWorkspace];
+        let reduce_motion: BOOL = msg_send![workspace, accessibilityDisplayShouldReduceMotion];
+        reduce_motion == YES
+    }
+
+    /// Covers `view` with an untagged copy showing its current material, and fades the copy out and removes it,
+    /// so changing the material of `view` right after looks like a crossfade.
+    unsafe fn fade_out_snapshot(view: id, duration: f64) {
+        let frame = NSView::frame(view);
+        let snapshot = NSVisualEffectView::initWithFrame_(NSVisualEffectView::alloc(nil), frame);
+        snapshot.autorelease();
+
+        let mask: NSAutoresizingMaskOptions = msg_send![view, autoresizingMask];
+        NSVisualEffectView::setAutoresizingMask_(snapshot, mask);
+        let material: NSInteger = msg_send![view, material];
+        let _: () = msg_send![snapshot, setMaterial: material];
+        let appearance: id = msg_send![view, appearance];
+        let _: () = msg_send![snapshot, setAppearance: appearance];
+        let blending_mode: NSUInteger = msg_send![view, blendingMode];
+        let _: () = msg_send![snapshot, setBlendingMode: blending_mode];
+        let state: NSUInteger = msg_send![view, state];
+        let _: () = msg_send![snapshot, setState: state];
+        let alpha: f64 = msg_send![view, alphaValue];
+        let _: () = msg_send![snapshot, setAlphaValue: alpha];
+        if is_at_least(10, 12, 0) {
+            let emphasized: BOOL = msg_send![view, isEmphasized];
+            let _: () = msg_send![snapshot, setEmphasized: emphasized];
+        }
+        let mask_image: id = msg_send![view, maskImage];
+        let _: () = msg_send![snapshot, setMaskImage: mask_image];
+        let layer: id = msg_send![view, layer];
+        if layer != nil {
+            let radius: f64 = msg_send![layer, cornerRadius];
+            if radius > 0.0 {
+                let _: () = msg_send![snapshot, setWantsLayer: YES];
+                let snapshot_layer: id = msg_send![snapshot, layer];
+                let _: () = msg_send![snapshot_layer, setCornerRadius: radius];
+                let _: () = msg_send![snapshot_layer, setMasksToBounds: YES];
+            }
+        }
+
+        let superview: id = msg_send![view, superview];
+        let _: () = msg_send![superview, addSubview: snapshot positioned: NSWindowOrderingMode::NSWindowAbove relativeTo: view];
+
+        let _: () = msg_send![class!(NSAnimationContext), beginGrouping];
+        let context: id = msg_send![class!(NSAnimationContext), currentContext];
+        let _: () = msg_send![context, setDuration: duration];
+        let animator: id = msg_send![snapshot, animator];
+        let _: () = msg_send![animator, setAlphaValue: 0.0f64];
+        let _: () = msg_send![class!(NSAnimationContext), endGrouping];
+
+        // objc blocks for a completion handler aren't available, the run loop removes the snapshot instead
+        let _: () = msg_send![snapshot, performSelector: sel!(removeFromSuperview) withObject: nil afterDelay: duration];
+    }
+
+    pub fn apply_titlebar_vibrancy(
+        window: id,
+        appearance: NSVisualEffectMaterial,
+    ) -> Result<(), Error> {
+        unsafe {
+            if !is_at_least(10, 11, 0) {
+                return Err(Error::UnsupportedPlatformVersion(
+                    "\"apply_titlebar_vibrancy()\" is only available on macOS 10.11 or newer.",
+                ));
+            }
+
+            if !msg_send![class!(NSThread), isMainThread] {
+                return Err(Error::NotMainThread(
+                    "\"apply_titlebar_vibrancy()\" can only be used on the main thread.",
+                ));
+            }
+
+            if !window
+                .styleMask()
+                .contains(NSWindowStyleMask::NSFullSizeContentViewWindowMask)
+            {
+                return Err(Error::InvalidArgument(
+                    "\"apply_titlebar_vibrancy()\" requires the content view to extend below the title bar, see \"make_titlebar_transparent()\".",
+                ));
+            }
+
+            let identifier = region_identifier(TITLEBAR_REGION);
+            apply_tagged_vibrancy(
+                window,
+                &identifier,
+                appearance,
+                VibrancyOptions {
+                    anchor: VibrancyAnchor::Top,
+                    ..Default::default()
+                },
+            )?;
+
+            // the bottom follows the content layout guide, which moves when the toolbar is shown or hidden
+            let view = find_tagged_view(window, &identifier);
+            let (container, _) = effect_container(window);
+            let guide: id = msg_send![window, contentLayoutGuide];
+            pin_view(
+                view,
+                (container, NSLayoutAttributeTop),
+                (guide, NSLayoutAttributeTop),
+            );
+        }
+        Ok(())
+    }
+
+    /// The region of `apply_vibrancy_region()` used by `apply_titlebar_vibrancy()`.
+    const TITLEBAR_REGION: &str = "titlebar";
+
+    #[allow(non_upper_case_globals)]
+    const NSLayoutAttributeTop: NSInteger = 3;
+    #[allow(non_upper_case_globals)]
+    const NSLayoutAttributeBottom: NSInteger = 4;
+    #[allow(non_upper_case_globals)]
+    const NSLayoutAttributeLeading: NSInteger = 5;
+    #[allow(non_upper_case_globals)]
+    const NSLayoutAttributeTrailing: NSInteger = 6;
+
+    /// Pins the leading and trailing edges of `view` to its superview, its top edge to `top`
+    /// and its bottom edge to `bottom`, given as an item and one of its attributes.
+    unsafe fn pin_view(view: id, top: (id, NSInteger), bottom: (id, NSInteger)) {
+        let superview: id = msg_send![view, superview];
+        let _: () = msg_send![view, setTranslatesAutoresizingMaskIntoConstraints: NO];
+        let constraints = [
+            layout_constraint(
+                view,
+                NSLayoutAttributeLeading,
+                superview,
+                NSLayoutAttributeLeading,
+            ),
+            layout_constraint(
+                view,
+                NSLayoutAttributeTrailing,
+                superview,
+                NSLayoutAttributeTrailing,
+            ),
+            layout_constraint(view, NSLayoutAttributeTop, top.0, top.1),
+            layout_constraint(view, NSLayoutAttributeBottom, bottom.0, bottom.1),
+        ];
+        let constraints: id = msg_send![class!(NSArray), arrayWithObjects: constraints.as_ptr() count: constraints.len()];
+        let _: () = msg_send![class!(NSLayoutConstraint), activateConstraints: constraints];
+    }
+
+    /// Returns a constraint making `attribute` of `item` equal to `to_attribute` of `to_item`.
+    unsafe fn layout_constraint(
+        item: id,
+        attribute: NSInteger,
+        to_item: id,
+        to_attribute: NSInteger,
+    ) -> id {
+        // `NSLayoutRelationEqual`
+        let relation: NSInteger = 0;
+        msg_send![class!(NSLayoutConstraint), constraintWithItem: item attribute: attribute relatedBy: relation toItem: to_item attribute: to_attribute multiplier: 1.0f64 constant: 0.0f64]
+    }
+
+    pub fn prepare_transparent_window(window: id) -> Result<(), Error> {
+        unsafe {
+            if !msg_send![class!(NSThread), isMainThread] {
+                return Err(Error::NotMainThread(
+                    "\"prepare_transparent_window()\" can only be used on the main thread.",
+                ));
+            }
+
+            // keep the values from before the first call, a second call would remember the clear color
+            let previous: id =
+                objc_getAssociatedObject(window, association_key(&PREVIOUS_BACKGROUND_KEY));
+            if previous == nil {
+                let background: id = msg_send![window, backgroundColor];
+                let opaque: BOOL = msg_send![window, isOpaque];
+                let opaque: id = msg_send![class!(NSNumber), numberWithBool: opaque];
+                let entries = [background, opaque];
+                let previous: id = msg_send![class!(NSArray), arrayWithObjects: entries.as_ptr() count: entries.len()];
+                objc_setAssociatedObject(
+                    window,
+                    association_key(&PREVIOUS_BACKGROUND_KEY),
+                    previous,
+                    OBJC_ASSOCIATION_RETAIN_NONATOMIC,
+                );
+            }
+
+            let clear: id = msg_send![class!(NSColor), clearColor];
+            window.setBackgroundColor_(clear);
+            window.setOpaque_(NO);
+        }
+        Ok(())
+    }
+
+    /// Restores the background changed by `prepare_transparent_window()`, if it was called.
+    unsafe fn restore_window_background(window: id) {
+        let previous: id =
+            objc_getAssociatedObject(window, association_key(&PREVIOUS_BACKGROUND_KEY));
+        if previous == nil {
+            return;
+        }
+        let background: id = msg_send![previous, objectAtIndex: 0 as NSUInteger];
+        let opaque: id = msg_send![previous, objectAtIndex: 1 as NSUInteger];
+        let opaque: BOOL = msg_send![opaque, boolValue];
+        window.setBackgroundColor_(background);
+        window.setOpaque_(opaque);
+        objc_setAssociatedObject(
+            window,
+            association_key(&PREVIOUS_BACKGROUND_KEY),
+            nil,
+            OBJC_ASSOCIATION_RETAIN_NONATOMIC,
+        );
+    }
+
+    /// The previous background is kept on the window itself, so it goes away with the window.
+    static PREVIOUS_BACKGROUND_KEY: u8 = 0;
+
+    /// Turns layer-backing on for `view`, remembering whether it was on before the first call.
+    unsafe fn force_layer_backing(view: id) {
+        let previous: id =
+            objc_getAssociatedObject(view, association_key(&PREVIOUS_WANTS_LAYER_KEY));
+        if previous == nil {
+            let wants_layer: BOOL = msg_send![view, wantsLayer];
+            let previous: id = msg_send![class!(NSNumber), numberWithBool: wants_layer];
+            set_associated_object(view, association_key(&PREVIOUS_WANTS_LAYER_KEY), previous);
+        }
+        let _: () = msg_send![view, setWantsLayer: YES];
+    }
+
+    /// Restores the layer-backing changed by `force_layer_backing()`, if it was called.
+    unsafe fn restore_layer_backing(view: id) {
+        let previous: id =
+            objc_getAssociatedObject(view, association_key(&PREVIOUS_WANTS_LAYER_KEY));
+        if previous == nil {
+            return;
+        }
+        let wants_layer: BOOL = msg_send![previous, boolValue];
+        let _: () = msg_send![view, setWantsLayer: wants_layer];
+        set_associated_object(view, association_key(&PREVIOUS_WANTS_LAYER_KEY), nil);
+    }
+
+    static PREVIOUS_WANTS_LAYER_KEY: u8 = 0;
+
+    /// Calls `callback` on a thread of its own with every value sent, in the order they were sent.
+    ///
+    /// The callback may take its time, like re-rendering web content, so it isn't called on the main thread.
+    /// The thread ends once the sender is dropped and the values sent before are handled.
+    fn spawn_callback_thread<T: Send + 'static>(callback: Box<dyn Fn(T) + Send>) -> Sender<T> {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            for value in receiver {
+                // a panicking callback still gets the values sent after
+                let _ = catch_unwind(AssertUnwindSafe(|| callback(value)));
+            }
+        });
+        sender
+    }
+
+    type AppearanceCallback = Sender<Appearance>;
+
+    pub fn observe_appearance_changes(
+        window: id,
+        callback: Box<dyn Fn(Appearance) + Send>,
+    ) -> Result<ObserverHandle, Error> {
+        unsafe {
+            if !is_at_least(10, 14, 0) {
+                return Err(Error::UnsupportedPlatformVersion(
+                    "\"observe_appearance_changes()\" is only available on macOS 10.14 or newer.",
+                ));
+            }
+
+            if !msg_send![class!(NSThread), isMainThread] {
+                return Err(Error::NotMainThread(
+                    "\"observe_appearance_changes()\" can only be used on the main thread.",
+                ));
+            }
+
+            let observer: id = msg_send![appearance_observer_class(), new];
+            let callback: AppearanceCallback = spawn_callback_thread(callback);
+            (*observer).set_ivar("callback", Box::into_raw(Box::new(callback)) as *mut c_void);
+            (*observer).set_ivar("window", window);
+
+            let _: () = msg_send![window, addObserver: observer forKeyPath: ns_string("effectiveAppearance") options: 0 as NSUInteger context: std::ptr::null_mut::<c_void>()];
+            // the observation must end before the window is deallocated, which may happen right after it is closed
+            let center: id = msg_send![class!(NSNotificationCenter), defaultCenter];
+            let _: () = msg_send![center, addObserver: observer selector: sel!(windowWillClose:) name: ns_string("NSWindowWillCloseNotification") object: window];
+
+            Ok(ObserverHandle {
+                observer,
+                remove: remove_appearance_observer,
+            })
+        }
+    }
+
+    fn remove_appearance_observer(observer: id) {
+        run_on_main(|| unsafe {
+            stop_observing(observer);
+            let callback = *(*observer).get_ivar::<*mut c_void>("callback");
+            drop(Box::from_raw(callback as *mut AppearanceCallback));
+            let _: () = msg_send![observer, release];
+        })
+    }
+
+    /// Removes the observer from its window, if the window wasn't closed yet.
+    unsafe fn stop_observing(observer: id) {
+        let window = *(*observer).get_ivar::<id>("window");
+        if window == nil {
+            return;
+        }
+        let _: () = msg_send![window, removeObserver: observer forKeyPath: ns_string("effectiveAppearance")];
+        let center: id = msg_send![class!(NSNotificationCenter), defaultCenter];
+        let _: () = msg_send![center, removeObserver: observer];
+        (*observer).set_ivar("window", nil);
+    }
+
+    /// Returns whether the effective appearance of the window is the dark or the light one, macOS 10.14+.
+    unsafe fn effective_appearance(window: id) -> Appearance {
+        let appearance: id = msg_send![window, effectiveAppearance];
+        let names = [
+            ns_string("NSAppearanceNameAqua"),
+            ns_string("NSAppearanceNameDarkAqua"),
+        ];
+        let names: id =
+            msg_send![class!(NSArray), arrayWithObjects: names.as_ptr() count: names.len()];
+        let name: id = msg_send![appearance, bestMatchFromAppearancesWithNames: names];
+        let dark: BOOL = msg_send![name, isEqualToString: ns_string("NSAppearanceNameDarkAqua")];
+        if dark == YES {
+            Appearance::Dark
+        } else {
+            Appearance::Light
+        }
+    }
+
+    /// Returns the class observing `effectiveAppearance`, declaring it on first use.
+    fn appearance_observer_class() -> &'static Class {
+        extern "C" fn observe_value(
+            this: &Object,
+            _: Sel,
+            _key_path: id,
+            _object: id,
+            _change: id,
+            _context: *mut c_void,
+        ) {
+            unsafe {
+                let window = *this.get_ivar::<id>("window");
+                if window == nil {
+                    return;
+                }
+                let appearance = effective_appearance(window);
+                let callback =
+                    &*(*this.get_ivar::<*mut c_void>("callback") as *const AppearanceCallback);
+                // only fails if the callback thread is gone
+                let _ = callback.send(appearance);
+            }
+        }
+
+        extern "C" fn window_will_close(this: &Object, _: Sel, _notification: id) {
+            unsafe { stop_observing(this as *const Object as id) };
+        }
+
+        get_or_declare_class(
+            "WindowVibrancyAppearanceObserver",
+            class!(NSObject),
+            |decl| {
+                decl.add_ivar::<*mut c_void>("callback");
+                decl.add_ivar::<id>("window");
+                unsafe {
+                    decl.add_method(
+                        sel!(observeValueForKeyPath:ofObject:change:context:),
+                        observe_value as extern "C" fn(&Object, Sel, id, id, id, *mut c_void),
+                    );
+                    decl.add_method(
+                        sel!(windowWillClose:),
+                        window_will_close as extern "C" fn(&Object, Sel, id),
+                    );
+                }
+            },
+        )
+    }
+
+    pub fn get_vibrancy_view(window: id) -> Result<Option<NonNull<c_void>>, Error> {
+        unsafe {
+            if !msg_send![class!(NSThread), isMainThread] {
+                return Err(Error::NotMainThread(
+                    "\"get_vibrancy_view()\" can only be used on the main thread.",
+                ));
+            }
+
+            Ok(NonNull::new(find_effect_view(window) as *mut c_void))
+        }
+    }
+
+    pub fn set_vibrancy_alpha(window: id, alpha: f64) -> Result<(), Error> {
+        unsafe {
+            if !msg_send![class!(NSThread), isMainThread] {
+                return Err(Error::NotMainThread(
+                    "\"set_vibrancy_alpha()\" can only be used on the main thread.",
+                ));
+            }
+
+            let alpha = clamp_alpha(
+                alpha,
+                "\"set_vibrancy_alpha()\" requires the alpha to be a number.",
+            )?;
+            let blurred_view = require_effect_view(
+                find_effect_view(window),
+                "\"set_vibrancy_alpha()\" requires vibrancy to be applied to the window with \"apply_vibrancy()\" first.",
+            )?;
+            let _: () = msg_send![blurred_view, setAlphaValue: alpha];
+        }
+        Ok(())
+    }
+
+    /// Returns `view`, the effect view found for a window, or the `not_applied` error if it is `nil`.
+    fn require_effect_view(view: id, not_applied: &'static str) -> Result<id, Error> {
+        if view == nil {
+            return Err(Error::EffectNotApplied(not_applied));
+        }
+        Ok(view)
+    }
+
+    /// Clamps `alpha` to `0.0..=1.0`, out of range values are a common result of animations.
+    fn clamp_alpha(alpha: f64, not_a_number: &'static str) -> Result<f64, Error> {
+        if alpha.is_nan() {
+            return Err(Error::InvalidArgument(not_a_number));
+        }
+        Ok(alpha.clamp(0.0, 1.0))
+    }
+
+    pub fn set_vibrancy_tint(window: id, tint: Option<crate::Color>) -> Result<(), Error> {
+        unsafe {
+            if !msg_send![class!(NSThread), isMainThread] {
+                return Err(Error::NotMainThread(
+                    "\"set_vibrancy_tint()\" can only be used on the main thread.",
+                ));
+            }
+
+            let blurred_view = require_effect_view(
+                find_effect_view(window),
+                "\"set_vibrancy_tint()\" requires vibrancy to be applied to the window with \"apply_vibrancy()\" first.",
+            )?;
+            set_tint(blurred_view, tint);
+        }
+        Ok(())
+    }
+
+    const TINT_VIEW_IDENTIFIER: &str = "app.tauri.window-vibrancy.tint";
+
+    /// Adds, updates or removes the tint of `blurred_view`. The tint is a subview filling the effect view,
+    /// so it follows its size and corner radius, and goes away with it in `clear_vibrancy()`.
+    unsafe fn set_tint(blurred_view: id, tint: Option<crate::Color>) {
+        let identifier = ns_string(TINT_VIEW_IDENTIFIER);
+        let subviews: id = msg_send![blurred_view, subviews];
+        let count: NSUInteger = msg_send![subviews, count];
+        let mut tint_view = nil;
+        for i in 0..count {
+            let view: id = msg_send![subviews, objectAtIndex: i];
+            let view_identifier: id = msg_send![view, identifier];
+            if view_identifier != nil {
+                let equal: BOOL = msg_send![view_identifier, isEqualToString: identifier];
+                if equal == YES {
+                    tint_view = view;
+                    break;
+                }
+            }
+        }
+
+        let (r, g, b, a) = match tint {
+            Some(tint) => tint,
+            None => {
+                if tint_view != nil {
+                    let _: () = msg_send![tint_view, removeFromSuperview];
+                }
+                return;
+            }
+        };
+
+        if tint_view == nil {
+            let tint_view_alloc: id = msg_send![class!(NSView), alloc];
+            tint_view = NSView::initWithFrame_(tint_view_alloc, NSView::bounds(blurred_view));
+            tint_view.autorelease();
+            let _: () = msg_send![tint_view, setIdentifier: identifier];
+            NSView::setAutoresizingMask_(tint_view, NSViewWidthSizable | NSViewHeightSizable);
+            let _: () = msg_send![tint_view, setWantsLayer: YES];
+            let _: () = msg_send![blurred_view, addSubview: tint_view];
+        }
+        let color: id = msg_send![class!(NSColor), colorWithSRGBRed: r as f64 / 255.0 green: g as f64 / 255.0 blue: b as f64 / 255.0 alpha: a as f64 / 255.0];
+        let color: *mut c_void = msg_send![color, CGColor];
+        let layer: id = msg_send![tint_view, layer];
+        let _: () = msg_send![layer, setBackgroundColor: color];
+    }
+
+    /// Keeps track of the effect views of a window, so they can be put back into place when entering
+    /// or exiting full screen moves the content of the window around. Everything is stored on the views
+    /// and the window themselves, so it goes away with them.
+    unsafe fn remember_effect_view(window: id, view: id, position: NSInteger, fills: bool) {
+        let position: id = msg_send![class!(NSNumber), numberWithInteger: position];
+        set_associated_object(view, association_key(&PLACEMENT_KEY), position);
+        let fills: id = msg_send![class!(NSNumber), numberWithBool: if fills { YES } else { NO }];
+        set_associated_object(view, association_key(&FILLS_CONTAINER_KEY), fills);
+
+        let mut views = objc_getAssociatedObject(window, association_key(&EFFECT_VIEWS_KEY));
+        if views == nil {
+            views = msg_send![class!(NSMutableArray), array];
+            set_associated_object(window, association_key(&EFFECT_VIEWS_KEY), views);
+        }
+        let contains: BOOL = msg_send![views, containsObject: view];
+        if contains == NO {
+            let _: () = msg_send![views, addObject: view];
+        }
+
+        let observer = objc_getAssociatedObject(window, association_key(&FULL_SCREEN_OBSERVER_KEY));
+        if observer == nil {
+            let observer: id = msg_send![full_screen_observer_class(), new];
+            // not retained, the window owns the observer
+            (*observer).set_ivar("window", window);
+            let center: id = msg_send![class!(NSNotificationCenter), defaultCenter];
+            for name in [
+                "NSWindowDidEnterFullScreenNotification",
+                "NSWindowDidExitFullScreenNotification",
+            ] {
+                let _: () = msg_send![center, addObserver: observer selector: sel!(windowDidChangeFullScreen:) name: ns_string(name) object: window];
+            }
+            set_associated_object(window, association_key(&FULL_SCREEN_OBSERVER_KEY), observer);
+            let _: () = msg_send![observer, release];
+        }
+    }
+
+    unsafe fn forget_effect_view(window: id, view: id) {
+        let views = objc_getAssociatedObject(window, association_key(&EFFECT_VIEWS_KEY));
+        if views != nil {
+            let _: () = msg_send![views, removeObject: view];
+        }
+    }
+
+    /// Puts the effect views of the window back into their container, and resizes the ones filling it.
+    unsafe fn restore_effect_views(window: id) {
+        let views = objc_getAssociatedObject(window, association_key(&EFFECT_VIEWS_KEY));
+        let ns_view: id = window.contentView();
+        if views == nil || ns_view == nil {
+            return;
+        }
+        let (container, relative_to) = effect_container(window);
+        let count: NSUInteger = msg_send![views, count];
+        for i in 0..count {
+            let view: id = msg_send![views, objectAtIndex: i];
+            let superview: id = msg_send![view, superview];
+            if superview != container {
+                let position = objc_getAssociatedObject(view, association_key(&PLACEMENT_KEY));
+                let position: NSInteger = msg_send![position, integerValue];
+                let _: () = msg_send![container, addSubview: view positioned: position relativeTo: relative_to];
+            }
+            let fills = objc_getAssociatedObject(view, association_key(&FILLS_CONTAINER_KEY));
+            let fills: BOOL = msg_send![fills, boolValue];
+            if fills == YES {
+                let bounds = NSView::bounds(ns_view);
+                let frame: NSRect = if container == ns_view {
+                    bounds
+                } else {
+                    msg_send![ns_view, convertRect: bounds toView: container]
+                };
+                let _: () = msg_send![view, setFrame: frame];
+            }
+        }
+    }
+
+    static PLACEMENT_KEY: u8 = 0;
+    static FILLS_CONTAINER_KEY: u8 = 0;
+    static EFFECT_VIEWS_KEY: u8 = 0;
+    static FULL_SCREEN_OBSERVER_KEY: u8 = 0;
+
+    fn association_key(key: &'static u8) -> *const c_void {
+        key as *const u8 as *const c_void
+    }
+
+    unsafe fn set_associated_object(object: id, key: *const c_void, value: id) {
+        objc_setAssociatedObject(object, key, value, OBJC_ASSOCIATION_RETAIN_NONATOMIC);
+    }
+
+    /// Returns the class restoring the effect views after full screen transitions, declaring it on first use.
+    fn full_screen_observer_class() -> &'static Class {
+        extern "C" fn window_did_change_full_screen(this: &Object, _: Sel, _notification: id) {
+            unsafe {
+                let window = *this.get_ivar::<id>("window");
+                restore_effect_views(window);
+            }
+        }
+
+        get_or_declare_class(
+            "WindowVibrancyFullScreenObserver",
+            class!(NSObject),
+            |decl| {
+                decl.add_ivar::<id>("window");
+                unsafe {
+                    decl.add_method(
+                        sel!(windowDidChangeFullScreen:),
+                        window_did_change_full_screen as extern "C" fn(&Object, Sel, id),
+                    );
+                }
+            },
+        )
+    }
+
+    pub fn ensure_vibrancy_behind_content(window: id) -> Result<(), Error> {
+        unsafe {
+            if !msg_send![class!(NSThread), isMainThread] {
+                return Err(Error::NotMainThread(
+                    "\"ensure_vibrancy_behind_content()\" can only be used on the main thread.",
+                ));
+            }
+
+            let (container, relative_to) = effect_container(window);
+            let below = NSWindowOrderingMode::NSWindowBelow.bits();
+            for view in tagged_views(window) {
+                let position = objc_getAssociatedObject(view, association_key(&PLACEMENT_KEY));
+                let position: NSInteger = msg_send![position, integerValue];
+                if position == below {
+                    let _: () = msg_send![container, addSubview: view positioned: below relativeTo: relative_to];
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_transparency_reduced() -> bool {
+        unsafe {
+            let workspace: id = msg_send![class!(NSWorkspace), sharedWorkspace];
+            let reduce_transparency: BOOL =
+                msg_send![workspace, accessibilityDisplayShouldReduceTransparency];
+            reduce_transparency == YES
+        }
+    }
+
+    type TransparencyCallback = Sender<bool>;
+
+    pub fn observe_transparency_changes(
+        callback: Box<dyn Fn(bool) + Send>,
+    ) -> Result<ObserverHandle, Error> {
+        unsafe {
+            if !is_at_least(10, 10, 0) {
+                return Err(Error::UnsupportedPlatformVersion(
+                    "\"observe_transparency_changes()\" is only available on macOS 10.10 or newer.",
+                ));
+            }
+
+            let observer: id = msg_send![transparency_observer_class(), new];
+            let callback: TransparencyCallback = spawn_callback_thread(callback);
+            (*observer).set_ivar("callback", Box::into_raw(Box::new(callback)) as *mut c_void);
+
+            let workspace: id = msg_send![class!(NSWorkspace), sharedWorkspace];
+            let center: id = msg_send![workspace, notificationCenter];
+            let _: () = msg_send![center, addObserver: observer selector: sel!(displayOptionsDidChange:) name: ns_string("NSWorkspaceAccessibilityDisplayOptionsDidChangeNotification") object: nil];
+
+            Ok(ObserverHandle {
+                observer,
+                remove: remove_transparency_observer,
+            })
+        }
+    }
+
+    fn remove_transparency_observer(observer: id) {
+        run_on_main(|| unsafe {
+            let workspace: id = msg_send![class!(NSWorkspace), sharedWorkspace];
+            let center: id = msg_send![workspace, notificationCenter];
+            let _: () = msg_send![center, removeObserver: observer];
+            let callback = *(*observer).get_ivar::<*mut c_void>("callback");
+            drop(Box::from_raw(callback as *mut TransparencyCallback));
+            let _: () = msg_send![observer, release];
+        })
+    }
+
+    /// Returns the class observing the accessibility display options, declaring it on first use.
+    fn transparency_observer_class() -> &'static Class {
+        // the notification is also posted when other display options like "Reduce motion" change
+        extern "C" fn display_options_did_change(this: &Object, _: Sel, _notification: id) {
+            unsafe {
+                let reduced = is_transparency_reduced();
+                let callback =
+                    &*(*this.get_ivar::<*mut c_void>("callback") as *const TransparencyCallback);
+                // only fails if the callback thread is gone
+                let _ = callback.send(reduced);
+            }
+        }
+
+        get_or_declare_class(
+            "WindowVibrancyTransparencyObserver",
+            class!(NSObject),
+            |decl| {
+                decl.add_ivar::<*mut c_void>("callback");
+                unsafe {
+                    decl.add_method(
+                        sel!(displayOptionsDidChange:),
+                        display_options_did_change as extern "C" fn(&Object, Sel, id),
+                    );
+                }
+            },
+        )
+    }
+
+    pub fn set_window_shadow(window: id, shadow: bool) -> Result<(), Error> {
+        unsafe {
+            if !msg_send![class!(NSThread), isMainThread] {
+                return Err(Error::NotMainThread(
+                    "\"set_window_shadow()\" can only be used on the main thread.",
+                ));
+            }
+
+            window.setHasShadow_(if shadow { YES } else { NO });
+            let _: () = msg_send![window, invalidateShadow];
+        }
+        Ok(())
+    }
+
+    pub fn propagate_to_sheets(
+        window: id,
+        material: NSVisualEffectMaterial,
+    ) -> Result<ObserverHandle, Error> {
+        unsafe {
+            if !msg_send![class!(NSThread), isMainThread] {
+                return Err(Error::NotMainThread(
+                    "\"propagate_to_sheets()\" can only be used on the main thread.",
+                ));
+            }
+
+            let observer: id = msg_send![sheet_observer_class(), new];
+            (*observer).set_ivar("window", window);
+            (*observer).set_ivar("material", material.to_raw());
+
+            let center: id = msg_send![class!(NSNotificationCenter), defaultCenter];
+            let _: () = msg_send![center, addObserver: observer selector: sel!(windowWillBeginSheet:) name: ns_string("NSWindowWillBeginSheetNotification") object: window];
+            let _: () = msg_send![center, addObserver: observer selector: sel!(windowWillClose:) name: ns_string("NSWindowWillCloseNotification") object: window];
+
+            Ok(ObserverHandle {
+                observer,
+                remove: remove_sheet_observer,
+            })
+        }
+    }
+
+    fn remove_sheet_observer(observer: id) {
+        run_on_main(|| unsafe {
+            let center: id = msg_send![class!(NSNotificationCenter), defaultCenter];
+            let _: () = msg_send![center, removeObserver: observer];
+            // a pending `applyToAttachedSheet:` keeps the observer alive until it ran, it checks the window
+            (*observer).set_ivar("window", nil);
+            let _: () = msg_send![observer, release];
+        })
+    }
+
+    /// Returns the class applying vibrancy to the sheets of a window, declaring it on first use.
+    fn sheet_observer_class() -> &'static Class {
+        extern "C" fn window_will_begin_sheet(this: &Object, _: Sel, _notification: id) {
+            unsafe {
+                let this = this as *const Object as id;
+                let window = *(*this).get_ivar::<id>("window");
+                let sheet: id = msg_send![window, attachedSheet];
+                if sheet != nil {
+                    apply_to_sheet(this, sheet);
+                } else {
+                    // the sheet isn't attached yet on some macOS versions, it is by the next run loop iteration
+                    let _: () = msg_send![this, performSelector: sel!(applyToAttachedSheet:) withObject: nil afterDelay: 0.0f64];
+                }
+            }
+        }
+
+        extern "C" fn apply_to_attached_sheet(this: &Object, _: Sel, _argument: id) {
+            unsafe {
+                let this = this as *const Object as id;
+                let window = *(*this).get_ivar::<id>("window");
+                if window == nil {
+                    return;
+                }
+                let sheet: id = msg_send![window, attachedSheet];
+                if sheet != nil {
+                    apply_to_sheet(this, sheet);
+                }
+            }
+        }
+
+        extern "C" fn window_will_close(this: &Object, _: Sel, _notification: id) {
+            unsafe {
+                let this = this as *const Object as id;
+                let center: id = msg_send![class!(NSNotificationCenter), defaultCenter];
+                let _: () = msg_send![center, removeObserver: this];
+                (*this).set_ivar("window", nil);
+            }
+        }
+
+        get_or_declare_class("WindowVibrancySheetObserver", class!(NSObject), |decl| {
+            decl.add_ivar::<id>("window");
+            decl.add_ivar::<isize>("material");
+            unsafe {
+                decl.add_method(
+                    sel!(windowWillBeginSheet:),
+                    window_will_begin_sheet as extern "C" fn(&Object, Sel, id),
+                );
+                decl.add_method(
+                    sel!(applyToAttachedSheet:),
+                    apply_to_attached_sheet as extern "C" fn(&Object, Sel, id),
+                );
+                decl.add_method(
+                    sel!(windowWillClose:),
+                    window_will_close as extern "C" fn(&Object, Sel, id),
+                );
+            }
+        })
+    }
+
+    /// Applies the material of the sheet observer to `sheet`, unless it is a save or open panel.
+    ///
+    /// A sheet presented again keeps the effect view of its previous presentation, which is updated
+    /// instead of adding another one, and the effect view goes away with the sheet.
+    unsafe fn apply_to_sheet(observer: id, sheet: id) {
+        // save and open panels draw their own background, an effect view would cover their file browser
+        let is_save_panel: BOOL = msg_send![sheet, isKindOfClass: class!(NSSavePanel)];
+        if is_save_panel == YES {
+            return;
+        }
+        let material = NSVisualEffectMaterial::from_raw(*(*observer).get_ivar::<isize>("material"));
+        // the sheet may not support vibrancy, there is no one to report the error to
+        let _ = apply_vibrancy(sheet, material, VibrancyOptions::default());
+    }
+
+    fn autoresizing_mask(anchor: VibrancyAnchor, view_flipped: bool) -> NSAutoresizingMaskOptions {
+        // the bottom margin is the min y margin, unless the content view is flipped
+        let (top_margin, bottom_margin) = if view_flipped {
+            (NSViewMinYMargin, NSViewMaxYMargin)
+        } else {
+            (NSViewMaxYMargin, NSViewMinYMargin)
+        };
+        match anchor {
+            VibrancyAnchor::Fill => NSViewWidthSizable | NSViewHeightSizable,
+            VibrancyAnchor::Left => NSViewHeightSizable | NSViewMaxXMargin,
+            VibrancyAnchor::Right => NSViewHeightSizable | NSViewMinXMargin,
+            VibrancyAnchor::Top => NSViewWidthSizable | bottom_margin,
+            VibrancyAnchor::Bottom => NSViewWidthSizable | top_margin,
+            VibrancyAnchor::Fixed => NSViewMaxXMargin | bottom_margin,
+        }
+    }
+
+    /// Returns the `NSAppearance` for `appearance`, `nil` for [`Appearance::System`].
+    unsafe fn ns_appearance(appearance: Appearance) -> id {
+        // the constants are named after their value, and `NSAppearanceNameDarkAqua`
+        // can't be linked against on macOS versions before 10.14
+        let name = match appearance {
+            Appearance::System => return nil,
+            Appearance::Light => "NSAppearanceNameAqua",
+            Appearance::Dark => "NSAppearanceNameDarkAqua",
+        };
+        let name = NSString::alloc(nil).init_str(name).autorelease();
+        msg_send![class!(NSAppearance), appearanceNamed: name]
+    }
+
+    /// Tags the effect view added by `apply_vibrancy()`, namespaced so that it can't clash
+    /// with the identifiers the app gives to its own views.
+    const EFFECT_VIEW_IDENTIFIER: &str = "app.tauri.window-vibrancy.effect-view";
+
+    /// The views of `apply_vibrancy_region()` are tagged with the region appended to [`EFFECT_VIEW_IDENTIFIER`],
+    /// which keeps the bookkeeping on the views themselves.
+    fn region_identifier(region: &str) -> String {
+        format!("{}.{}", EFFECT_VIEW_IDENTIFIER, region)
+    }
+
+    unsafe fn ns_string(string: &str) -> id {
+        NSString::alloc(nil).init_str(string).autorelease()
+    }
+
+    unsafe fn rust_string(string: id) -> String {
+        let bytes = string.UTF8String();
+        if bytes.is_null() {
+            return String::new();
+        }
+        CStr::from_ptr(bytes).to_string_lossy().into_owned()
+    }
+
+    /// Returns the effect view added by `apply_vibrancy()` to the window, or `nil`.
+    ///
+    /// Every function touching the effect view must look it up through this,
+    /// so the effect views of the app itself are never modified.
+    unsafe fn find_effect_view(window: id) -> id {
+        find_tagged_view(window, EFFECT_VIEW_IDENTIFIER)
+    }
+
+    /// Returns the effect view of the window tagged with `identifier`, or `nil`.
+    unsafe fn find_tagged_view(window: id, identifier: &str) -> id {
+        effect_views(window)
+            .into_iter()
+            .find(|(_, view_identifier)| view_identifier == identifier)
+            .map_or(nil, |(view, _)| view)
+    }
+
+    /// Returns all effect views added by this crate to the window, regions included.
+    unsafe fn tagged_views(window: id) -> Vec<id> {
+        effect_views(window)
+            .into_iter()
+            .filter(|(_, view_identifier)| is_tagged_identifier(view_identifier))
+            .map(|(view, _)| view)
+            .collect()
+    }
+
+    /// Returns whether `identifier` is the one of the effect view of `apply_vibrancy()` or of a region,
+    /// effect views the app tagged with its own identifiers are left alone.
+    fn is_tagged_identifier(identifier: &str) -> bool {
+        identifier == EFFECT_VIEW_IDENTIFIER || identifier.starts_with(&region_identifier(""))
+    }
+
+    /// Returns the view the effect views are added to, and the subview they are placed relative to, or `nil`.
+    ///
+    /// The content view of a `contentViewController`, like the `NSHostingView` of SwiftUI, lays out its
+    /// subviews itself and drops or covers the ones it doesn't know, so the effect views go into
+    /// the superview of the content view then, right behind or in front of the content view.
+    unsafe fn effect_container(window: id) -> (id, id) {
+        let ns_view: id = window.contentView();
+        let controller: id = msg_send![window, contentViewController];
+        if controller != nil {
+            let superview: id = msg_send![ns_view, superview];
+            if superview != nil {
+                return (superview, ns_view);
+            }
+        }
+        (ns_view, nil)
+    }
+
+    /// Returns the effect views in the container of the window that have an identifier, with their identifier.
+    unsafe fn effect_views(window: id) -> Vec<(id, String)> {
+        let (container, _) = effect_container(window);
+        let subviews: id = msg_send![container, subviews];
+        let count: NSUInteger = msg_send![subviews, count];
+        let mut views = Vec::new();
+        for i in 0..count {
+            let view: id = msg_send![subviews, objectAtIndex: i];
+            let is_effect_view: BOOL = msg_send![view, isKindOfClass: class!(NSVisualEffectView)];
+            if is_effect_view != YES {
+                continue;
+            }
+            let view_identifier: id = msg_send![view, identifier];
+            if view_identifier != nil {
+                views.push((view, rust_string(view_identifier)));
+            }
+        }
+        views
+    }
+
+    pub fn macos_version() -> (u64, u64, u64) {
+        unsafe {
+            let process_info: id = msg_send![class!(NSProcessInfo), processInfo];
+            let responds: BOOL =
+                msg_send![process_info, respondsToSelector: sel!(operatingSystemVersion)];
+            if responds == NO {
+                // `operatingSystemVersion` was added in macOS 10.10, which is the oldest version this crate supports
+                return (10, 9, 0);
+            }
+            let version: NSOperatingSystemVersion = msg_send![process_info, operatingSystemVersion];
+            (
+                version.major as u64,
+                version.minor as u64,
+                version.patch as u64,
+            )
+        }
+    }
+
+    /// Whether the running macOS version is `major.minor.patch` or newer.
+    fn is_at_least(major: u64, minor: u64, patch: u64) -> bool {
+        version_at_least(macos_version(), (major, minor, patch))
+    }
+
+    /// Whether the `current` version is the `wanted` one or newer, comparing major, minor and patch in that order.
+    fn version_at_least(current: (u64, u64, u64), wanted: (u64, u64, u64)) -> bool {
+        current >= wanted
+    }
+
+    #[repr(C)]
+    struct NSOperatingSystemVersion {
+        major: NSInteger,
+        minor: NSInteger,
+        patch: NSInteger,
+    }
+
+    #[allow(non_upper_case_globals)]
+    const NSWindowStyleMaskNonactivatingPanel: NSUInteger = 1 << 7;
+
+    /// Non-activating panels never become the key or the main window, so following
+    /// their active state would leave the effect view inactive for good.
+    unsafe fn default_state(window: id) -> NSVisualEffectState {
+        let is_panel: BOOL = msg_send![window, isKindOfClass: class!(NSPanel)];
+        if is_panel == YES && window.styleMask().bits() & NSWindowStyleMaskNonactivatingPanel != 0 {
+            NSVisualEffectState::Active
+        } else {
+            NSVisualEffectState::FollowsWindowActiveState
+        }
+    }
+
+    // macos 10.10+
+    // https://developer.apple.com/documentation/appkit/nsvisualeffectview
+    #[allow(non_snake_case)]
+    trait NSVisualEffectView: Sized {
+        unsafe fn alloc(_: Self) -> id {
+            msg_send![class!(NSVisualEffectView), alloc]
+        }
+
+        unsafe fn init(self) -> id;
+        unsafe fn initWithFrame_(self, frameRect: NSRect) -> id;
+        unsafe fn bounds(self) -> NSRect;
+        unsafe fn frame(self) -> NSRect;
+        unsafe fn setFrameSize(self, frameSize: NSSize);
+        unsafe fn setFrameOrigin(self, frameOrigin: NSPoint);
+
+        unsafe fn superview(self) -> id;
+        unsafe fn removeFromSuperview(self);
+        unsafe fn setAutoresizingMask_(self, autoresizingMask: NSAutoresizingMaskOptions);
+
+        // API_AVAILABLE(macos(10.12));
+        unsafe fn isEmphasized(self) -> BOOL;
+        // API_AVAILABLE(macos(10.12));
+        unsafe fn setEmphasized_(self, emphasized: BOOL);
+
+        unsafe fn setMaterial_(self, material: NSVisualEffectMaterial);
+        unsafe fn setState_(self, state: NSVisualEffectState);
+        unsafe fn setBlendingMode_(self, mode: NSVisualEffectBlendingMode);
+    }
+
+    #[allow(non_snake_case)]
+    impl NSVisualEffectView for id {
+        unsafe fn init(self) -> id {
+            msg_send![self, init]
+        }
+
+        unsafe fn initWithFrame_(self, frameRect: NSRect) -> id {
+            msg_send![self, initWithFrame: frameRect]
+        }
+
+        unsafe fn bounds(self) -> NSRect {
+            msg_send![self, bounds]
+        }
+
+        unsafe fn frame(self) -> NSRect {
+            msg_send![self, frame]
+        }
+
+        unsafe fn setFrameSize(self, frameSize: NSSize) {
+            msg_send![self, setFrameSize: frameSize]
+        }
+
+        unsafe fn setFrameOrigin(self, frameOrigin: NSPoint) {
+            msg_send![self, setFrameOrigin: frameOrigin]
+        }
+
+        unsafe fn superview(self) -> id {
+            msg_send![self, superview]
+        }
+
+        unsafe fn removeFromSuperview(self) {
+            msg_send![self, removeFromSuperview]
+        }
+
+        unsafe fn setAutoresizingMask_(self, autoresizingMask: NSAutoresizingMaskOptions) {
+            msg_send![self, setAutoresizingMask: autoresizingMask]
+        }
+
+        // API_AVAILABLE(macos(10.12));
+        unsafe fn isEmphasized(self) -> BOOL {
+            msg_send![self, isEmphasized]
+        }
+
+        // API_AVAILABLE(macos(10.12));
+        unsafe fn setEmphasized_(self, emphasized: BOOL) {
+            msg_send![self, setEmphasized: emphasized]
+        }
+
+        unsafe fn setMaterial_(self, material: NSVisualEffectMaterial) {
+            msg_send![self, setMaterial: material.to_raw()]
+        }
+
+        unsafe fn setState_(self, state: NSVisualEffectState) {
+            msg_send![self, setState: state]
+        }
+
+        unsafe fn setBlendingMode_(self, mode: NSVisualEffectBlendingMode) {
             msg_send![self, setBlendingMode: mode]
         }
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// A non-`nil` object for checks that never message it.
+        fn object() -> id {
+            NonNull::<Object>::dangling().as_ptr()
+        }
+
+        #[test]
+        fn content_view_requires_window_and_content_view() {
+            let window = object();
+            let content_view = object();
+            assert_eq!(
+                require_content_view(window, content_view, "no window", "no view").ok(),
+                Some(content_view)
+            );
+            assert!(matches!(
+                require_content_view(nil, nil, "no window", "no view"),
+                Err(Error::InvalidWindowHandle("no window"))
+            ));
+            assert!(matches!(
+                require_content_view(window, nil, "no window", "no view"),
+                Err(Error::NoWindowContentView("no view"))
+            ));
+        }
+
+        #[test]
+        fn effect_view_is_required() {
+            let view = object();
+            assert_eq!(require_effect_view(view, "not applied").ok(), Some(view));
+            assert!(matches!(
+                require_effect_view(nil, "not applied"),
+                Err(Error::EffectNotApplied("not applied"))
+            ));
+        }
+
+        #[test]
+        fn clamp_alpha_rejects_nan() {
+            assert!(matches!(
+                clamp_alpha(f64::NAN, "not a number"),
+                Err(Error::InvalidArgument("not a number"))
+            ));
+        }
+
+        #[test]
+        fn clamp_alpha_clamps_to_unit_range() {
+            assert_eq!(clamp_alpha(-0.5, "").ok(), Some(0.0));
+            assert_eq!(clamp_alpha(f64::NEG_INFINITY, "").ok(), Some(0.0));
+            assert_eq!(clamp_alpha(1.5, "").ok(), Some(1.0));
+            assert_eq!(clamp_alpha(f64::INFINITY, "").ok(), Some(1.0));
+            assert_eq!(clamp_alpha(0.0, "").ok(), Some(0.0));
+            assert_eq!(clamp_alpha(0.25, "").ok(), Some(0.25));
+            assert_eq!(clamp_alpha(1.0, "").ok(), Some(1.0));
+        }
+
+        #[test]
+        fn version_at_least_compares_every_component() {
+            assert!(version_at_least((10, 14, 0), (10, 14, 0)));
+            assert!(!version_at_least((10, 13, 6), (10, 14, 0)));
+            assert!(version_at_least((11, 0, 0), (10, 14, 0)));
+            // the minor version is compared as a number, 10.10 is newer than 10.9
+            assert!(version_at_least((10, 10, 0), (10, 9, 0)));
+            assert!(!version_at_least((10, 13, 6), (10, 13, 7)));
+            assert!(version_at_least((10, 14, 1), (10, 14, 0)));
+            assert!(!version_at_least((10, 14, 0), (11, 0, 0)));
+        }
+
+        #[test]
+        #[allow(deprecated)]
+        fn material_macos_version_groups_materials_by_release() {
+            use NSVisualEffectMaterial::*;
+            for &material in &[AppearanceBased, Light, Dark, Titlebar, Selection] {
+                assert_eq!(material_macos_version(material), (10, 10), "{:?}", material);
+            }
+            for &material in &[MediumLight, UltraDark, Menu, Popover, Sidebar] {
+                assert_eq!(material_macos_version(material), (10, 11), "{:?}", material);
+            }
+            for &material in &[
+                HeaderView,
+                Sheet,
+                WindowBackground,
+                HudWindow,
+                FullScreenUI,
+                Tooltip,
+                ContentBackground,
+                UnderWindowBackground,
+                UnderPageBackground,
+            ] {
+                assert_eq!(material_macos_version(material), (10, 14), "{:?}", material);
+            }
+            // left to `resolve_auto()` and AppKit
+            assert_eq!(material_macos_version(Auto), (10, 10));
+            assert_eq!(material_macos_version(Custom(23)), (10, 10));
+            assert_eq!(material_macos_version(Custom(-1)), (10, 10));
+        }
+
+        #[test]
+        fn material_macos_version_covers_every_known_material() {
+            // AppKit added the raw values in order, so a newer material never has a lower one
+            let mut previous = (10, 10);
+            for &(material, _) in super::super::MATERIALS.iter() {
+                let version = material_macos_version(material);
+                assert!(version >= previous, "{:?}", material);
+                previous = version;
+            }
+        }
+
+        #[test]
+        fn tagged_identifiers_are_the_ones_of_the_crate() {
+            assert!(is_tagged_identifier(EFFECT_VIEW_IDENTIFIER));
+            assert!(is_tagged_identifier(&region_identifier("sidebar")));
+            // the region prefix itself, for an empty region name
+            assert!(is_tagged_identifier(&region_identifier("")));
+        }
+
+        #[test]
+        fn identifiers_of_the_app_are_left_alone() {
+            // an unrelated effect view of the app, like one from Interface Builder
+            assert!(!is_tagged_identifier("com.example.app.sidebar"));
+            assert!(!is_tagged_identifier(""));
+            // no separator, so not a region
+            assert!(!is_tagged_identifier(&format!(
+                "{}-sidebar",
+                EFFECT_VIEW_IDENTIFIER
+            )));
+            assert!(!is_tagged_identifier("app.tauri.window-vibrancy"));
+            assert!(!is_tagged_identifier(TINT_VIEW_IDENTIFIER));
+            // identifiers are compared case-sensitively, like `isEqualToString:`
+            assert!(!is_tagged_identifier(
+                &EFFECT_VIEW_IDENTIFIER.to_uppercase()
+            ));
+        }
+
+        fn rect(x: f64, y: f64, width: f64, height: f64) -> NSRect {
+            NSRect::new(NSPoint::new(x, y), NSSize::new(width, height))
+        }
+
+        fn parts(rect: NSRect) -> (f64, f64, f64, f64) {
+            (
+                rect.origin.x,
+                rect.origin.y,
+                rect.size.width,
+                rect.size.height,
+            )
+        }
+
+        fn frame(flipped: bool) -> Option<VibrancyFrame> {
+            Some(VibrancyFrame {
+                x: 10.0,
+                y: 20.0,
+                width: 100.0,
+                height: 50.0,
+                flipped,
+            })
+        }
+
+        #[test]
+        fn effect_view_frame_converts_flipped_frames() {
+            let bounds = rect(0.0, 0.0, 400.0, 300.0);
+            // measured the same way as the content view
+            assert_eq!(
+                parts(effect_view_frame(bounds, false, frame(false), None)),
+                (10.0, 20.0, 100.0, 50.0)
+            );
+            assert_eq!(
+                parts(effect_view_frame(bounds, true, frame(true), None)),
+                (10.0, 20.0, 100.0, 50.0)
+            );
+            // measured from the other edge, 300 - 20 - 50
+            assert_eq!(
+                parts(effect_view_frame(bounds, false, frame(true), None)),
+                (10.0, 230.0, 100.0, 50.0)
+            );
+            assert_eq!(
+                parts(effect_view_frame(bounds, true, frame(false), None)),
+                (10.0, 230.0, 100.0, 50.0)
+            );
+        }
+
+        #[test]
+        fn effect_view_frame_follows_offset_bounds() {
+            let bounds = rect(5.0, 7.0, 400.0, 300.0);
+            assert_eq!(
+                parts(effect_view_frame(bounds, false, frame(false), None)),
+                (15.0, 27.0, 100.0, 50.0)
+            );
+            assert_eq!(
+                parts(effect_view_frame(bounds, false, frame(true), None)),
+                (15.0, 237.0, 100.0, 50.0)
+            );
+        }
+
+        #[test]
+        fn effect_view_frame_prefers_frame_over_layout_rect() {
+            let bounds = rect(0.0, 0.0, 400.0, 300.0);
+            let layout_rect = rect(0.0, 0.0, 400.0, 272.0);
+            assert_eq!(
+                parts(effect_view_frame(bounds, false, None, None)),
+                (0.0, 0.0, 400.0, 300.0)
+            );
+            assert_eq!(
+                parts(effect_view_frame(bounds, false, None, Some(layout_rect))),
+                (0.0, 0.0, 400.0, 272.0)
+            );
+            assert_eq!(
+                parts(effect_view_frame(
+                    bounds,
+                    false,
+                    frame(false),
+                    Some(layout_rect)
+                )),
+                (10.0, 20.0, 100.0, 50.0)
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(deprecated)]
+    fn materials_round_trip_through_raw_values() {
+        for &(material, raw) in MATERIALS.iter() {
+            assert_eq!(material.to_raw(), raw);
+            assert_eq!(NSVisualEffectMaterial::from_raw(raw), material);
+            assert_eq!(
+                NSVisualEffectMaterial::from_raw(material.to_raw()),
+                material
+            );
+        }
+    }
+
+    #[test]
+    fn raw_values_are_unique() {
+        for (i, &(_, raw)) in MATERIALS.iter().enumerate() {
+            assert!(MATERIALS[i + 1..].iter().all(|&(_, other)| other != raw));
+        }
+    }
+
+    #[test]
+    fn custom_materials_round_trip() {
+        for &raw in &[14, 16, 19, 20, 23, 1000, -1, isize::MAX] {
+            let material = NSVisualEffectMaterial::Custom(raw);
+            assert_eq!(material.to_raw(), raw);
+            assert_eq!(NSVisualEffectMaterial::from_raw(raw), material);
+        }
+        // a raw value the crate knows comes back as the named material
+        assert_eq!(
+            NSVisualEffectMaterial::from_raw(NSVisualEffectMaterial::Custom(21).to_raw()),
+            NSVisualEffectMaterial::UnderWindowBackground
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn auto_picks_material_per_version() {
+        use NSVisualEffectMaterial::*;
+        assert_eq!(
+            NSVisualEffectMaterial::auto_for_version(10, 9),
+            AppearanceBased
+        );
+        assert_eq!(
+            NSVisualEffectMaterial::auto_for_version(10, 10),
+            AppearanceBased
+        );
+        assert_eq!(
+            NSVisualEffectMaterial::auto_for_version(10, 13),
+            AppearanceBased
+        );
+        assert_eq!(
+            NSVisualEffectMaterial::auto_for_version(10, 14),
+            UnderWindowBackground
+        );
+        assert_eq!(
+            NSVisualEffectMaterial::auto_for_version(10, 15),
+            UnderWindowBackground
+        );
+        assert_eq!(
+            NSVisualEffectMaterial::auto_for_version(11, 0),
+            UnderWindowBackground
+        );
+        assert_eq!(
+            NSVisualEffectMaterial::auto_for_version(14, 2),
+            UnderWindowBackground
+        );
+    }
+
+    #[test]
+    fn auto_avoids_deprecated_materials() {
+        for &(since, material) in NSVisualEffectMaterial::AUTO_MATERIALS.iter() {
+            assert_ne!(material, NSVisualEffectMaterial::Auto);
+            // deprecated materials only stand for `Auto` before they were deprecated in 10.14
+            if since >= (10, 14) {
+                assert_eq!(material.modern_equivalent(), None);
+            }
+        }
+    }
+
+    #[test]
+    fn auto_table_is_newest_first() {
+        let table = NSVisualEffectMaterial::AUTO_MATERIALS;
+        for pair in table.windows(2) {
+            assert!(pair[0].0 > pair[1].0);
+        }
+    }
+
+    #[test]
+    fn auto_resolves_to_a_concrete_material() {
+        let resolved = NSVisualEffectMaterial::Auto.resolve_auto();
+        assert_ne!(resolved, NSVisualEffectMaterial::Auto);
+        assert_eq!(NSVisualEffectMaterial::Auto.to_raw(), resolved.to_raw());
+        assert_eq!(
+            NSVisualEffectMaterial::Sidebar.resolve_auto(),
+            NSVisualEffectMaterial::Sidebar
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn deprecated_materials_have_pinned_modern_equivalents() {
+        use NSVisualEffectMaterial::*;
+        assert_eq!(
+            AppearanceBased.modern_equivalent(),
+            Some((WindowBackground, Appearance::System))
+        );
+        assert_eq!(
+            Light.modern_equivalent(),
+            Some((WindowBackground, Appearance::Light))
+        );
+        assert_eq!(
+            MediumLight.modern_equivalent(),
+            Some((UnderWindowBackground, Appearance::Light))
+        );
+        assert_eq!(
+            Dark.modern_equivalent(),
+            Some((UnderWindowBackground, Appearance::Dark))
+        );
+        assert_eq!(
+            UltraDark.modern_equivalent(),
+            Some((HudWindow, Appearance::Dark))
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn other_materials_have_no_modern_equivalent() {
+        use NSVisualEffectMaterial::*;
+        let deprecated = [AppearanceBased, Light, MediumLight, Dark, UltraDark];
+        for &(material, _) in MATERIALS.iter() {
+            if !deprecated.contains(&material) {
+                assert_eq!(material.modern_equivalent(), None, "{:?}", material);
+            }
+        }
+        assert_eq!(Auto.modern_equivalent(), None);
+        assert_eq!(Custom(23).modern_equivalent(), None);
+        // a modern equivalent is never deprecated itself
+        for &material in &deprecated {
+            let (modern, _) = material.modern_equivalent().unwrap();
+            assert_eq!(modern.modern_equivalent(), None);
+        }
+    }
 }
@@ -2,298 +2,2760 @@
 // SPDX-License-Identifier: Apache-2.0
 // SPDX-License-Identifier: MIT
 
-#![cfg(target_os = "windows")]
-#![allow(non_snake_case)]
-#![allow(non_camel_case_types)]
+use crate::Color;
 
-use std::ffi::c_void;
-pub use windows_sys::Win32::{
-    Foundation::*,
-    Graphics::{Dwm::*, Gdi::*},
-    System::{LibraryLoader::*, SystemInformation::*},
-};
+/// <https://learn.microsoft.com/en-us/windows/win32/api/dwmapi/ne-dwmapi-dwm_systembackdrop_type>
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SystemBackdropType {
+    /// Let DWM automatically decide the system-drawn backdrop for the window.
+    Auto,
+    /// Don't draw any system backdrop.
+    None,
+    /// Mica effect.
+    Mica,
+    /// Acrylic effect.
+    Acrylic,
+    /// Tabbed effect, a Mica variant with a stronger tint from the desktop background.
+    Tabbed,
+    /// A raw `DWM_SYSTEMBACKDROP_TYPE` value, passed to DWM as is.
+    ///
+    /// This is unchecked and meant for trying out backdrop types that are not documented yet,
+    /// it is only gated behind Windows 11 and DWM may ignore or reject values it doesn't know.
+    Raw(i32),
+}
 
-use crate::{Color, Error};
+/// <https://learn.microsoft.com/en-us/windows/win32/api/dwmapi/ne-dwmapi-dwm_window_corner_preference>
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CornerPreference {
+    /// Let the system decide whether or not to round window corners.
+    Default,
+    /// Never round window corners.
+    DoNotRound,
+    /// Round the corners if appropriate.
+    Round,
+    /// Round the corners if appropriate, with a small radius.
+    RoundSmall,
+}
 
-pub fn apply_blur(hwnd: HWND, color: Option<Color>) -> Result<(), Error> {
-    if is_win7() {
-        let bb = DWM_BLURBEHIND {
-            dwFlags: DWM_BB_ENABLE,
-            fEnable: true.into(),
-            hRgnBlur: HRGN::default(),
-            fTransitionOnMaximized: 0,
-        };
-        unsafe {
-            let _ = DwmEnableBlurBehindWindow(hwnd, &bb);
+/// The color of the 1px border DWM draws around the window.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BorderColor {
+    /// Don't draw the window border.
+    None,
+    /// Use the system default border color.
+    Default,
+    /// Use the given color, its alpha channel is ignored.
+    Color(Color),
+}
+
+/// Options for [`apply_blur_with_options`](crate::apply_blur_with_options)
+/// and [`apply_acrylic_with_options`](crate::apply_acrylic_with_options).
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EffectOptions {
+    /// The tint color of the effect.
+    pub color: Option<Color>,
+    /// Fade the effect in instead of enabling it instantly.
+    ///
+    /// This is best-effort, some Windows builds ignore it
+    /// and it has no effect when the effect is applied through the system backdrop.
+    pub animate: bool,
+    /// The accent policy flags, `None` keeps the defaults of the effect.
+    ///
+    /// Only used when the effect is applied through `SetWindowCompositionAttribute`,
+    /// that is blur on Windows 10 and Windows 11 and acrylic on Windows 10 and Windows 11 21H2.
+    pub flags: Option<AccentFlags>,
+    /// Apply the effect even if the window has the `WS_EX_LAYERED` extended style.
+    ///
+    /// Layered windows drawn with `UpdateLayeredWindow` bypass the surface DWM draws the effect behind,
+    /// so the effect is either invisible or breaks their per-pixel alpha and an error is returned by default.
+    /// Layered windows that only use `SetLayeredWindowAttributes` for a constant alpha or a color key
+    /// still get the effect, set this if you know your window works that way.
+    pub allow_layered: bool,
+    /// Temporarily disable the effect while the window is maximized or covers its whole monitor,
+    /// where the effect samples the screen edges and wastes GPU time, and restore it afterwards.
+    ///
+    /// The window is subclassed to know when it is maximized and restored,
+    /// clearing the effect removes the subclass again.
+    ///
+    /// Only blur and acrylic can be suspended, since only they take options: mica, tabbed and the other
+    /// system backdrops stay applied while the window is maximized.
+    pub suspend_when_maximized: bool,
+    /// Don't apply the effect when the user turned off transparency effects,
+    /// see [`are_transparency_effects_enabled`](crate::are_transparency_effects_enabled).
+    ///
+    /// The effect previously applied by the crate is cleared instead and
+    /// [`Applied::SkippedTransparencyDisabled`] is returned,
+    /// so the application can draw an opaque background that looks right.
+    pub skip_when_transparency_disabled: bool,
+    /// Don't apply the effect while a high contrast theme is active,
+    /// see [`is_high_contrast_enabled`](crate::is_high_contrast_enabled).
+    ///
+    /// The effect previously applied by the crate is cleared instead and [`Applied::SkippedHighContrast`] is returned.
+    pub skip_when_high_contrast: bool,
+}
+
+/// Flags of the undocumented accent policy used by blur and acrylic on Windows 10 and Windows 11,
+/// controlling which edges of the window get a border drawn and how the tint color is used.
+///
+/// Flags can be combined with `|`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct AccentFlags(pub u32);
+
+impl AccentFlags {
+    /// No flags, no border is drawn.
+    pub const NONE: Self = Self(0);
+    /// Tint the effect with the gradient color, this is the default for blur.
+    pub const GRADIENT_COLOR: Self = Self(0x2);
+    /// Draw a border on the left edge of the window.
+    pub const DRAW_LEFT_BORDER: Self = Self(0x20);
+    /// Draw a border on the top edge of the window.
+    pub const DRAW_TOP_BORDER: Self = Self(0x40);
+    /// Draw a border on the right edge of the window.
+    pub const DRAW_RIGHT_BORDER: Self = Self(0x80);
+    /// Draw a border on the bottom edge of the window.
+    pub const DRAW_BOTTOM_BORDER: Self = Self(0x100);
+    /// Draw a border on all edges of the window.
+    pub const DRAW_ALL_BORDERS: Self = Self(0x1e0);
+
+    /// Returns the raw value of the flags.
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Returns `true` if all the flags in `other` are set.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl std::ops::BitOr for AccentFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitOrAssign for AccentFlags {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// The state of the undocumented accent policy, which selects the effect drawn behind the window.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccentState {
+    /// No effect.
+    Disabled,
+    /// An opaque gradient color.
+    EnableGradient,
+    /// A translucent gradient color.
+    EnableTransparentGradient,
+    /// Blur, tinted with the gradient color on Windows 10 and newer.
+    EnableBlurBehind,
+    /// Acrylic, available on Windows 10 v1709 and newer.
+    EnableAcrylicBlurBehind,
+    /// The host backdrop, available on Windows 10 v1903 and newer.
+    EnableHostBackdrop,
+    /// A raw accent state value, passed to `SetWindowCompositionAttribute` as is.
+    Raw(u32),
+}
+
+impl AccentState {
+    #[cfg(target_os = "windows")]
+    fn to_raw(self) -> u32 {
+        match self {
+            AccentState::Disabled => 0,
+            AccentState::EnableGradient => 1,
+            AccentState::EnableTransparentGradient => 2,
+            AccentState::EnableBlurBehind => 3,
+            AccentState::EnableAcrylicBlurBehind => 4,
+            AccentState::EnableHostBackdrop => 5,
+            AccentState::Raw(state) => state,
         }
-    } else if is_win10_swca() || is_win11() {
-        unsafe {
-            SetWindowCompositionAttribute(hwnd, ACCENT_STATE::ACCENT_ENABLE_BLURBEHIND, color);
+    }
+}
+
+/// The undocumented accent policy set with `SetWindowCompositionAttribute`,
+/// see [`set_accent_policy`](crate::set_accent_policy).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AccentPolicy {
+    /// The effect drawn behind the window.
+    pub state: AccentState,
+    /// Which edges get a border drawn and how the gradient color is used.
+    pub flags: AccentFlags,
+    /// The tint color in the `0xAABBGGRR` format.
+    pub gradient_color: u32,
+    /// Lets DWM animate the change when non-zero, this is best-effort.
+    pub animation_id: u32,
+}
+
+impl AccentPolicy {
+    /// Creates a policy with the given state, no flags and no tint color.
+    pub const fn new(state: AccentState) -> Self {
+        Self {
+            state,
+            flags: AccentFlags::NONE,
+            gradient_color: 0,
+            animation_id: 0,
         }
-    } else {
-        return Err(Error::UnsupportedPlatformVersion(
-      "\"apply_blur()\" is only available on Windows 7, Windows 10 v1809 or newer and Windows 11.",
-    ));
     }
-    Ok(())
+
+    /// Sets the tint color of the policy, converting it from RGBA to the `0xAABBGGRR` format DWM expects.
+    pub const fn with_gradient_color(mut self, color: Color) -> Self {
+        self.gradient_color = color_to_gradient(color);
+        self
+    }
+}
+
+/// Converts an RGBA [`Color`] to the `0xAABBGGRR` gradient color of the accent policy,
+/// so red and blue swap places: `(255, 0, 0, 128)` becomes `0x800000FF`.
+const fn color_to_gradient(color: Color) -> u32 {
+    (color.0 as u32) | (color.1 as u32) << 8 | (color.2 as u32) << 16 | (color.3 as u32) << 24
+}
+
+/// The mechanism an effect was applied with,
+/// returned by [`apply_blur_with_options`](crate::apply_blur_with_options),
+/// [`apply_acrylic_with_options`](crate::apply_acrylic_with_options)
+/// and [`apply_vibrancy_with_options`](crate::apply_vibrancy_with_options).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Applied {
+    /// `DwmEnableBlurBehindWindow`, used for blur on Windows 7. The tint color is not drawn.
+    DwmBlurBehind,
+    /// The undocumented accent policy of `SetWindowCompositionAttribute`,
+    /// used for blur on Windows 8 and newer and for acrylic on Windows 10 and Windows 11 before 22H2.
+    /// The tint color is not drawn on Windows 8 and 8.1.
+    AccentPolicy,
+    /// The system backdrop, used for acrylic on Windows 11 22H2 and newer. The tint color is not drawn.
+    SystemBackdrop,
+    /// Nothing was applied because transparency effects are turned off,
+    /// see [`EffectOptions::skip_when_transparency_disabled`].
+    SkippedTransparencyDisabled,
+    /// Nothing was applied because a high contrast theme is active,
+    /// see [`EffectOptions::skip_when_high_contrast`].
+    SkippedHighContrast,
+    /// An `NSVisualEffectView`, used for vibrancy on macOS.
+    VisualEffectView,
+    /// Nothing was applied because "Reduce transparency" is turned on on macOS,
+    /// see [`VibrancyOptions::skip_when_transparency_reduced`](crate::VibrancyOptions::skip_when_transparency_reduced).
+    SkippedReducedTransparency,
+}
+
+/// The margins to extend the window frame by, see [`extend_frame_into_client_area`](crate::extend_frame_into_client_area).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Margins {
+    /// Extend the frame into the whole client area, also known as "sheet of glass".
+    Sheet,
+    /// Extend the frame by the given amount of pixels on each side, zero margins go back to the default frame.
+    Explicit {
+        left: i32,
+        right: i32,
+        top: i32,
+        bottom: i32,
+    },
+}
+
+/// A value for [`set_dwm_window_attribute`](crate::set_dwm_window_attribute).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DwmAttributeValue {
+    /// Passed as a `BOOL`.
+    Bool(bool),
+    /// Passed as a `DWORD`, also used for enum attributes.
+    U32(u32),
+    /// Passed as a `COLORREF`, the alpha channel is dropped.
+    Color(Color),
 }
 
-pub fn clear_blur(hwnd: HWND) -> Result<(), Error> {
-    if is_win7() {
+#[cfg(target_os = "windows")]
+pub use internal::*;
+
+#[cfg(target_os = "windows")]
+#[allow(non_snake_case)]
+#[allow(non_camel_case_types)]
+mod internal {
+    use std::{
+        collections::HashMap,
+        ffi::c_void,
+        sync::{
+            atomic::{AtomicU32, AtomicUsize, Ordering},
+            Mutex, MutexGuard, PoisonError,
+        },
+    };
+
+    use once_cell::sync::{Lazy, OnceCell};
+    pub use windows_sys::Win32::{
+        Foundation::*,
+        Graphics::{Dwm::*, Gdi::*},
+        System::{
+            LibraryLoader::*,
+            Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS},
+            Registry::{RegGetValueW, HKEY_CURRENT_USER, RRF_RT_REG_DWORD},
+            SystemInformation::*,
+            Threading::{GetCurrentProcessId, GetCurrentThreadId},
+        },
+        UI::{
+            Accessibility::{HCF_HIGHCONTRASTON, HIGHCONTRASTW},
+            Controls::MARGINS,
+            Shell::{DefSubclassProc, RemoveWindowSubclass, SetWindowSubclass},
+            WindowsAndMessaging::{
+                CallNextHookEx, GetWindowLongW, GetWindowRect, GetWindowThreadProcessId, IsWindow,
+                IsZoomed, RegisterWindowMessageW, SendMessageTimeoutW, SetWindowPos,
+                SetWindowsHookExW, SystemParametersInfoW, UnhookWindowsHookEx, CWPSTRUCT,
+                GWL_EXSTYLE, GWL_STYLE, HC_ACTION, SIZE_MAXIMIZED, SIZE_RESTORED, SMTO_ABORTIFHUNG,
+                SPI_GETHIGHCONTRAST, SWP_FRAMECHANGED, SWP_NOACTIVATE, SWP_NOMOVE,
+                SWP_NOOWNERZORDER, SWP_NOSIZE, SWP_NOZORDER, USER_DEFAULT_SCREEN_DPI,
+                WH_CALLWNDPROC, WM_DPICHANGED, WM_DWMCOMPOSITIONCHANGED, WM_ENTERSIZEMOVE,
+                WM_EXITSIZEMOVE, WM_NCDESTROY, WM_SETTINGCHANGE, WM_SIZE, WM_THEMECHANGED,
+                WS_CHILD, WS_EX_LAYERED, WS_EX_NOREDIRECTIONBITMAP,
+            },
+        },
+    };
+
+    use super::{
+        color_to_gradient, AccentFlags, AccentPolicy, AccentState, Applied, BorderColor,
+        CornerPreference, DwmAttributeValue, EffectOptions, Margins, SystemBackdropType,
+    };
+    use crate::{Color, Error};
+
+    pub fn apply_blur(hwnd: HWND, options: EffectOptions) -> Result<Applied, Error> {
+        if !supports_blur() {
+            return Err(Error::UnsupportedPlatformVersion(
+                "\"apply_blur()\" is only available on Windows 7, Windows 8, Windows 8.1, Windows 10 v1809 or newer and Windows 11.",
+            ));
+        }
+        if !options.allow_layered && is_layered(hwnd) {
+            return Err(Error::LayeredWindow(
+                "\"apply_blur()\" doesn't work on a window with the WS_EX_LAYERED style, set \"EffectOptions::allow_layered\" to apply it anyway.",
+            ));
+        }
+        if has_no_redirection_bitmap(hwnd) {
+            return Err(Error::NoRedirectionBitmap(
+                "\"apply_blur()\" doesn't work on a window with the WS_EX_NOREDIRECTIONBITMAP style.",
+            ));
+        }
+        if options.skip_when_transparency_disabled && !transparency_effects_enabled() {
+            clear_effect(hwnd)?;
+            return Ok(Applied::SkippedTransparencyDisabled);
+        }
+        if options.skip_when_high_contrast && high_contrast_enabled() {
+            clear_effect(hwnd)?;
+            return Ok(Applied::SkippedHighContrast);
+        }
+        if is_win7() && !is_composition_enabled() {
+            return Err(Error::DwmCompositionDisabled(
+                "\"apply_blur()\" requires desktop composition to be enabled on Windows 7.",
+            ));
+        }
+        ensure_top_level(hwnd)?;
+        switch_effect(hwnd, Effect::Blur)?;
+        capture_snapshot(hwnd)?;
+        let applied = if is_win7() {
+            // also reset the blur region, in case `apply_blur_region()` was used before
+            let bb = DWM_BLURBEHIND {
+                dwFlags: DWM_BB_ENABLE | DWM_BB_BLURREGION,
+                fEnable: true.into(),
+                hRgnBlur: HRGN::default(),
+                fTransitionOnMaximized: 0,
+            };
+            dwm_enable_blur_behind_window(hwnd, &bb)?;
+            Applied::DwmBlurBehind
+        } else {
+            // Windows 8 and 8.1 don't draw the gradient color
+            let accent_options = if is_win8() {
+                EffectOptions {
+                    color: None,
+                    ..options
+                }
+            } else {
+                options
+            };
+            unsafe {
+                SetWindowCompositionAttribute(hwnd, AccentState::EnableBlurBehind, accent_options)?;
+            }
+            Applied::AccentPolicy
+        };
+        record_effect_with_options(hwnd, Effect::Blur, options)?;
+        suspend_if_maximized(hwnd)?;
+        Ok(applied)
+    }
+
+    pub fn apply_blur_region(hwnd: HWND, rects: &[(i32, i32, i32, i32)]) -> Result<(), Error> {
+        apply_blur_region_at(hwnd, rects, window_dpi(hwnd))
+    }
+
+    /// Applies blur to `rects` given for `dpi`, scaled to the current DPI of the window.
+    fn apply_blur_region_at(
+        hwnd: HWND,
+        rects: &[(i32, i32, i32, i32)],
+        dpi: u32,
+    ) -> Result<(), Error> {
+        if !is_win7() {
+            return Err(Error::UnsupportedPlatformVersion(
+                "\"apply_blur_region()\" is only available on Windows 7.",
+            ));
+        }
+        if !is_composition_enabled() {
+            return Err(Error::DwmCompositionDisabled(
+                "\"apply_blur_region()\" requires desktop composition to be enabled.",
+            ));
+        }
+        if rects.is_empty() {
+            return apply_blur(hwnd, EffectOptions::default()).map(|_| ());
+        }
+        ensure_top_level(hwnd)?;
+        switch_effect(hwnd, Effect::Blur)?;
+        capture_snapshot(hwnd)?;
+        let region = create_region(&scale_rects(rects, dpi, window_dpi(hwnd)))?;
         let bb = DWM_BLURBEHIND {
-            dwFlags: DWM_BB_ENABLE,
-            fEnable: false.into(),
-            hRgnBlur: HRGN::default(),
+            dwFlags: DWM_BB_ENABLE | DWM_BB_BLURREGION,
+            fEnable: true.into(),
+            hRgnBlur: region,
             fTransitionOnMaximized: 0,
         };
-        unsafe {
-            let _ = DwmEnableBlurBehindWindow(hwnd, &bb);
+        let result = dwm_enable_blur_behind_window(hwnd, &bb);
+        // DWM keeps its own copy of the region
+        unsafe { DeleteObject(region) };
+        result?;
+        update_window_state(hwnd, |state| {
+            state.record(Some(Effect::Blur), EffectOptions::default());
+            state.blur_region = rects.to_vec();
+            state.blur_region_dpi = dpi;
+        })
+    }
+
+    pub fn clear_blur(hwnd: HWND) -> Result<bool, Error> {
+        if !supports_blur() {
+            return Err(Error::UnsupportedPlatformVersion(
+                "\"clear_blur()\" is only available on Windows 7, Windows 8, Windows 8.1, Windows 10 v1809 or newer and Windows 11.",
+            ));
         }
-    } else if is_win10_swca() || is_win11() {
-        unsafe {
-            SetWindowCompositionAttribute(hwnd, ACCENT_STATE::ACCENT_DISABLED, None);
+        if is_win7() {
+            let bb = DWM_BLURBEHIND {
+                dwFlags: DWM_BB_ENABLE | DWM_BB_BLURREGION,
+                fEnable: false.into(),
+                hRgnBlur: HRGN::default(),
+                fTransitionOnMaximized: 0,
+            };
+            dwm_enable_blur_behind_window(hwnd, &bb)?;
+        } else {
+            restore_accent_policy(hwnd)?;
         }
-    } else {
-        return Err(Error::UnsupportedPlatformVersion(
-      "\"clear_blur()\" is only available on Windows 7, Windows 10 v1809 or newer and Windows 11.",
-    ));
+        forget_effect(hwnd, Effect::Blur)
     }
-    Ok(())
-}
 
-pub fn apply_acrylic(hwnd: HWND, color: Option<Color>) -> Result<(), Error> {
-    if is_win11_dwmsbt() {
-        unsafe {
-            DwmSetWindowAttribute(
-                hwnd,
-                DWMWA_USE_IMMERSIVE_DARK_MODE,
-                &DWM_SYSTEMBACKDROP_TYPE::DWMSBT_TRANSIENTWINDOW as *const _ as _,
-                4,
-            );
+    pub fn apply_transparent(hwnd: HWND, color: Option<Color>) -> Result<(), Error> {
+        if !supports_transparent() {
+            return Err(Error::UnsupportedPlatformVersion(
+                "\"apply_transparent()\" is only available on Windows 10 v1809 or newer and Windows 11.",
+            ));
         }
-    } else if is_win10_swca() || is_win11() {
+        ensure_top_level(hwnd)?;
+        switch_effect(hwnd, Effect::Transparent)?;
+        capture_snapshot(hwnd)?;
         unsafe {
             SetWindowCompositionAttribute(
                 hwnd,
-                ACCENT_STATE::ACCENT_ENABLE_ACRYLICBLURBEHIND,
+                AccentState::EnableTransparentGradient,
+                EffectOptions {
+                    color,
+                    ..Default::default()
+                },
+            )?;
+        }
+        record_effect_with_options(
+            hwnd,
+            Effect::Transparent,
+            EffectOptions {
                 color,
-            );
+                ..Default::default()
+            },
+        )
+    }
+
+    pub fn clear_transparent(hwnd: HWND) -> Result<bool, Error> {
+        if !supports_transparent() {
+            return Err(Error::UnsupportedPlatformVersion(
+                "\"clear_transparent()\" is only available on Windows 10 v1809 or newer and Windows 11.",
+            ));
         }
-    } else {
-        return Err(Error::UnsupportedPlatformVersion(
-            "\"apply_acrylic()\" is only available on Windows 10 v1809 or newer and Windows 11.",
-        ));
+        restore_accent_policy(hwnd)?;
+        forget_effect(hwnd, Effect::Transparent)
     }
-    Ok(())
-}
 
-pub fn clear_acrylic(hwnd: HWND) -> Result<(), Error> {
-    if is_win11_dwmsbt() {
-        unsafe {
-            DwmSetWindowAttribute(
-                hwnd,
-                DWMWA_USE_IMMERSIVE_DARK_MODE,
-                &DWM_SYSTEMBACKDROP_TYPE::DWMSBT_DISABLE as *const _ as _,
-                4,
-            );
+    pub fn apply_acrylic(hwnd: HWND, options: EffectOptions) -> Result<Applied, Error> {
+        if !supports_acrylic() {
+            return Err(Error::UnsupportedPlatformVersion(
+                "\"apply_acrylic()\" is only available on Windows 10 v1709 or newer and Windows 11.",
+            ));
         }
-    } else if is_win10_swca() || is_win11() {
-        unsafe {
-            SetWindowCompositionAttribute(hwnd, ACCENT_STATE::ACCENT_DISABLED, None);
+        if !options.allow_layered && is_layered(hwnd) {
+            return Err(Error::LayeredWindow(
+                "\"apply_acrylic()\" doesn't work on a window with the WS_EX_LAYERED style, set \"EffectOptions::allow_layered\" to apply it anyway.",
+            ));
+        }
+        if has_no_redirection_bitmap(hwnd) {
+            return Err(Error::NoRedirectionBitmap(
+                "\"apply_acrylic()\" doesn't work on a window with the WS_EX_NOREDIRECTIONBITMAP style.",
+            ));
+        }
+        if options.skip_when_transparency_disabled && !transparency_effects_enabled() {
+            clear_effect(hwnd)?;
+            return Ok(Applied::SkippedTransparencyDisabled);
+        }
+        if options.skip_when_high_contrast && high_contrast_enabled() {
+            clear_effect(hwnd)?;
+            return Ok(Applied::SkippedHighContrast);
         }
-    } else {
-        return Err(Error::UnsupportedPlatformVersion(
-            "\"clear_acrylic()\" is only available on Windows 10 v1809 or newer and Windows 11.",
-        ));
+        ensure_top_level(hwnd)?;
+        switch_effect(hwnd, Effect::Acrylic)?;
+        capture_snapshot(hwnd)?;
+        update_window_state(hwnd, |state| state.resize_workaround = None)?;
+        let applied = if is_win11_dwmsbt() {
+            set_system_backdrop(hwnd, SystemBackdropType::Acrylic)?;
+            Applied::SystemBackdrop
+        } else {
+            unsafe {
+                SetWindowCompositionAttribute(hwnd, AccentState::EnableAcrylicBlurBehind, options)?;
+            }
+            Applied::AccentPolicy
+        };
+        record_effect_with_options(hwnd, Effect::Acrylic, options)?;
+        suspend_if_maximized(hwnd)?;
+        Ok(applied)
     }
-    Ok(())
-}
 
-pub fn apply_mica(hwnd: HWND) -> Result<(), Error> {
-    if is_win11_dwmsbt() {
-        unsafe {
-            DwmSetWindowAttribute(
-                hwnd,
-                DWMWA_SYSTEMBACKDROP_TYPE,
-                &DWM_SYSTEMBACKDROP_TYPE::DWMSBT_MAINWINDOW as *const _ as _,
-                4,
-            );
+    pub fn apply_acrylic_with_resize_workaround(
+        hwnd: HWND,
+        color: Option<Color>,
+    ) -> Result<(), Error> {
+        let options = EffectOptions {
+            color,
+            ..Default::default()
+        };
+        apply_acrylic(hwnd, options)?;
+        // the system backdrop doesn't suffer from the resize lag
+        if !is_win11_dwmsbt() {
+            update_window_state(hwnd, |state| state.resize_workaround = Some(options))?;
         }
-    } else if is_win11() {
-        unsafe {
-            DwmSetWindowAttribute(hwnd, DWMWA_MICA_EFFECT, &1 as *const _ as _, 4);
+        Ok(())
+    }
+
+    pub fn clear_acrylic(hwnd: HWND) -> Result<bool, Error> {
+        if !supports_acrylic() {
+            return Err(Error::UnsupportedPlatformVersion(
+                "\"clear_acrylic()\" is only available on Windows 10 v1709 or newer and Windows 11.",
+            ));
+        }
+        update_window_state(hwnd, |state| state.resize_workaround = None)?;
+        if is_win11_dwmsbt() {
+            restore_system_backdrop(hwnd)?;
+        } else {
+            restore_accent_policy(hwnd)?;
         }
-    } else {
-        return Err(Error::UnsupportedPlatformVersion(
-            "\"apply_mica()\" is only available on Windows 11.",
-        ));
+        forget_effect(hwnd, Effect::Acrylic)
     }
-    Ok(())
-}
 
-pub fn clear_mica(hwnd: HWND) -> Result<(), Error> {
-    if is_win11_dwmsbt() {
+    pub fn apply_host_backdrop_accent(hwnd: HWND, color: Option<Color>) -> Result<(), Error> {
+        if !supports_host_backdrop_accent() {
+            return Err(Error::UnsupportedPlatformVersion(
+                "\"apply_host_backdrop_accent()\" is only available on Windows 10 v1903 or newer and Windows 11.",
+            ));
+        }
+        ensure_top_level(hwnd)?;
+        switch_effect(hwnd, Effect::HostBackdropAccent)?;
+        capture_snapshot(hwnd)?;
         unsafe {
-            DwmSetWindowAttribute(
+            SetWindowCompositionAttribute(
                 hwnd,
-                DWMWA_SYSTEMBACKDROP_TYPE,
-                &DWM_SYSTEMBACKDROP_TYPE::DWMSBT_DISABLE as *const _ as _,
-                4,
-            );
+                AccentState::EnableHostBackdrop,
+                EffectOptions {
+                    color,
+                    ..Default::default()
+                },
+            )?;
         }
-    } else if is_win11() {
-        unsafe {
-            DwmSetWindowAttribute(hwnd, DWMWA_MICA_EFFECT, &0 as *const _ as _, 4);
+        record_effect_with_options(
+            hwnd,
+            Effect::HostBackdropAccent,
+            EffectOptions {
+                color,
+                ..Default::default()
+            },
+        )
+    }
+
+    pub fn clear_host_backdrop_accent(hwnd: HWND) -> Result<bool, Error> {
+        if !supports_host_backdrop_accent() {
+            return Err(Error::UnsupportedPlatformVersion(
+                "\"clear_host_backdrop_accent()\" is only available on Windows 10 v1903 or newer and Windows 11.",
+            ));
         }
-    } else {
-        return Err(Error::UnsupportedPlatformVersion(
-            "\"clear_mica()\" is only available on Windows 11.",
-        ));
+        restore_accent_policy(hwnd)?;
+        forget_effect(hwnd, Effect::HostBackdropAccent)
     }
-    Ok(())
-}
 
-fn get_function_impl(library: &str, function: &str) -> Option<FARPROC> {
-    assert_eq!(library.chars().last(), Some('\0'));
-    assert_eq!(function.chars().last(), Some('\0'));
+    pub fn apply_mica(hwnd: HWND) -> Result<(), Error> {
+        if !supports_mica() {
+            return Err(Error::UnsupportedPlatformVersion(
+                "\"apply_mica()\" is only available on Windows 11.",
+            ));
+        }
+        ensure_top_level(hwnd)?;
+        switch_effect(hwnd, Effect::Mica)?;
+        capture_snapshot(hwnd)?;
+        if is_win11_dwmsbt() {
+            set_system_backdrop(hwnd, SystemBackdropType::Mica)?;
+        } else {
+            dwm_set_window_attribute(hwnd, DWMWA_MICA_EFFECT, &BOOL::from(true))?;
+        }
+        record_effect(hwnd, Some(Effect::Mica))
+    }
 
-    let module = unsafe { LoadLibraryA(library.as_ptr()) };
-    if module == 0 {
-        return None;
+    pub fn clear_mica(hwnd: HWND) -> Result<bool, Error> {
+        if !supports_mica() {
+            return Err(Error::UnsupportedPlatformVersion(
+                "\"clear_mica()\" is only available on Windows 11.",
+            ));
+        }
+        if is_win11_dwmsbt() {
+            restore_system_backdrop(hwnd)?;
+            // the legacy attribute may have been set by an older version of the app or the crate,
+            // newer builds are free to reject it so this is best-effort
+            let _ = dwm_set_window_attribute(hwnd, DWMWA_MICA_EFFECT, &BOOL::from(false));
+        } else {
+            dwm_set_window_attribute(hwnd, DWMWA_MICA_EFFECT, &BOOL::from(false))?;
+        }
+        forget_effect(hwnd, Effect::Mica)
     }
-    Some(unsafe { GetProcAddress(module, function.as_ptr()) })
-}
 
-macro_rules! get_function {
-    ($lib:expr, $func:ident) => {
-        get_function_impl(concat!($lib, '\0'), concat!(stringify!($func), '\0'))
-            .map(|f| std::mem::transmute::<::windows_sys::Win32::Foundation::FARPROC, $func>(f))
-    };
-}
+    pub fn apply_tabbed(hwnd: HWND) -> Result<(), Error> {
+        if !supports_tabbed() {
+            return Err(Error::UnsupportedPlatformVersion(
+                "\"apply_tabbed()\" is only available on Windows 11 22H2 or newer.",
+            ));
+        }
+        ensure_top_level(hwnd)?;
+        switch_effect(hwnd, Effect::Tabbed)?;
+        capture_snapshot(hwnd)?;
+        set_system_backdrop(hwnd, SystemBackdropType::Tabbed)?;
+        record_effect(hwnd, Some(Effect::Tabbed))
+    }
 
-/// Returns a tuple of (major, minor, buildnumber)
-fn get_windows_ver() -> Option<(u32, u32, u32)> {
-    type RtlGetVersion = unsafe extern "system" fn(*mut OSVERSIONINFOW) -> i32;
-    let handle = unsafe { get_function!("ntdll.dll", RtlGetVersion) };
-    if let Some(rtl_get_version) = handle {
-        unsafe {
-            let mut vi = OSVERSIONINFOW {
-                dwOSVersionInfoSize: 0,
-                dwMajorVersion: 0,
-                dwMinorVersion: 0,
-                dwBuildNumber: 0,
-                dwPlatformId: 0,
-                szCSDVersion: [0; 128],
-            };
+    pub fn clear_tabbed(hwnd: HWND) -> Result<bool, Error> {
+        if !supports_tabbed() {
+            return Err(Error::UnsupportedPlatformVersion(
+                "\"clear_tabbed()\" is only available on Windows 11 22H2 or newer.",
+            ));
+        }
+        restore_system_backdrop(hwnd)?;
+        forget_effect(hwnd, Effect::Tabbed)
+    }
 
-            let status = (rtl_get_version)(&mut vi as _);
+    pub fn apply_system_backdrop(
+        hwnd: HWND,
+        backdrop_type: SystemBackdropType,
+    ) -> Result<(), Error> {
+        if let SystemBackdropType::Raw(_) = backdrop_type {
+            if !is_win11() {
+                return Err(Error::UnsupportedPlatformVersion(
+                    "\"apply_system_backdrop()\" with a raw value is only available on Windows 11.",
+                ));
+            }
+        } else if !is_win11_dwmsbt() {
+            return Err(Error::UnsupportedPlatformVersion(
+                "\"apply_system_backdrop()\" is only available on Windows 11 22H2 or newer.",
+            ));
+        }
+        ensure_top_level(hwnd)?;
+        let effect = match backdrop_type {
+            SystemBackdropType::Auto => None,
+            backdrop_type => Some(Effect::SystemBackdrop(backdrop_type)),
+        };
+        match effect {
+            Some(effect) => {
+                switch_effect(hwnd, effect)?;
+                capture_snapshot(hwnd)?;
+            }
+            None => clear_effect(hwnd)?,
+        }
+        set_system_backdrop(hwnd, backdrop_type)?;
+        record_effect(hwnd, effect)
+    }
 
-            if status >= 0 {
-                Some((vi.dwMajorVersion, vi.dwMinorVersion, vi.dwBuildNumber))
-            } else {
-                None
+    pub fn set_immersive_dark_mode(hwnd: HWND, dark: bool) -> Result<(), Error> {
+        let attribute = if is_at_least_build(18985) {
+            DWMWA_USE_IMMERSIVE_DARK_MODE
+        } else if is_at_least_build(17763) {
+            DWMWA_USE_IMMERSIVE_DARK_MODE_BEFORE_20H1
+        } else {
+            return Err(Error::UnsupportedPlatformVersion(
+                "\"set_immersive_dark_mode()\" is only available on Windows 10 v1809 or newer and Windows 11.",
+            ));
+        };
+        dwm_set_window_attribute(hwnd, attribute, &BOOL::from(dark))
+    }
+
+    pub fn follow_system_theme(hwnd: HWND) -> Result<(), Error> {
+        set_immersive_dark_mode(hwnd, dark_mode_enabled())?;
+        update_window_state(hwnd, |state| state.follow_system_theme = true)
+    }
+
+    pub fn unfollow_system_theme(hwnd: HWND) -> Result<(), Error> {
+        update_window_state(hwnd, |state| state.follow_system_theme = false)
+    }
+
+    /// Reads the apps theme of the user, a missing value means the light theme,
+    /// which is also the only theme before Windows 10 v1809.
+    pub fn dark_mode_enabled() -> bool {
+        is_dark(read_registry_dword(
+            "Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize",
+            "AppsUseLightTheme",
+        ))
+    }
+
+    /// Whether the `AppsUseLightTheme` registry value selects the dark mode, which is only the case for `0`.
+    /// The value is missing before Windows 10 v1607, apps are light there.
+    fn is_dark(apps_use_light_theme: Option<u32>) -> bool {
+        apps_use_light_theme == Some(0)
+    }
+
+    pub fn set_rounded_corners(hwnd: HWND, preference: CornerPreference) -> Result<(), Error> {
+        if !is_win11() {
+            return Err(Error::UnsupportedPlatformVersion(
+                "\"set_rounded_corners()\" is only available on Windows 11.",
+            ));
+        }
+        let value = match preference {
+            CornerPreference::Default => DWMWCP_DEFAULT,
+            CornerPreference::DoNotRound => DWMWCP_DONOTROUND,
+            CornerPreference::Round => DWMWCP_ROUND,
+            CornerPreference::RoundSmall => DWMWCP_ROUNDSMALL,
+        };
+        dwm_set_window_attribute(hwnd, DWMWA_WINDOW_CORNER_PREFERENCE, &value)
+    }
+
+    pub fn set_border_color(hwnd: HWND, color: BorderColor) -> Result<(), Error> {
+        if !is_win11() {
+            return Err(Error::UnsupportedPlatformVersion(
+                "\"set_border_color()\" is only available on Windows 11.",
+            ));
+        }
+        let value = match color {
+            BorderColor::None => DWMWA_COLOR_NONE,
+            BorderColor::Default => DWMWA_COLOR_DEFAULT,
+            BorderColor::Color(color) => color_to_colorref(color),
+        };
+        dwm_set_window_attribute(hwnd, DWMWA_BORDER_COLOR, &value)
+    }
+
+    pub fn set_caption_color(hwnd: HWND, color: Option<Color>) -> Result<(), Error> {
+        if !is_win11() {
+            return Err(Error::UnsupportedPlatformVersion(
+                "\"set_caption_color()\" is only available on Windows 11.",
+            ));
+        }
+        let value = color.map_or(DWMWA_COLOR_DEFAULT, color_to_colorref);
+        dwm_set_window_attribute(hwnd, DWMWA_CAPTION_COLOR, &value)
+    }
+
+    pub fn set_caption_text_color(hwnd: HWND, color: Option<Color>) -> Result<(), Error> {
+        if !is_win11() {
+            return Err(Error::UnsupportedPlatformVersion(
+                "\"set_caption_text_color()\" is only available on Windows 11.",
+            ));
+        }
+        let value = color.map_or(DWMWA_COLOR_DEFAULT, color_to_colorref);
+        dwm_set_window_attribute(hwnd, DWMWA_TEXT_COLOR, &value)
+    }
+
+    pub fn set_host_backdrop_brush(hwnd: HWND, enable: bool) -> Result<(), Error> {
+        if !supports_host_backdrop_brush() {
+            return Err(Error::UnsupportedPlatformVersion(
+                "\"set_host_backdrop_brush()\" is only available on Windows 10 v2004 or newer and Windows 11.",
+            ));
+        }
+        dwm_set_window_attribute(hwnd, DWMWA_USE_HOSTBACKDROPBRUSH, &BOOL::from(enable))
+    }
+
+    pub fn get_system_backdrop(hwnd: HWND) -> Result<SystemBackdropType, Error> {
+        if !is_win11_dwmsbt() {
+            return Err(Error::UnsupportedPlatformVersion(
+                "\"get_system_backdrop()\" is only available on Windows 11 22H2 or newer.",
+            ));
+        }
+        let value: i32 = dwm_get_window_attribute(hwnd, DWMWA_SYSTEMBACKDROP_TYPE)?;
+        Ok(match value {
+            v if v == DWM_SYSTEMBACKDROP_TYPE::DWMSBT_AUTO as i32 => SystemBackdropType::Auto,
+            v if v == DWM_SYSTEMBACKDROP_TYPE::DWMSBT_DISABLE as i32 => SystemBackdropType::None,
+            v if v == DWM_SYSTEMBACKDROP_TYPE::DWMSBT_MAINWINDOW as i32 => SystemBackdropType::Mica,
+            v if v == DWM_SYSTEMBACKDROP_TYPE::DWMSBT_TRANSIENTWINDOW as i32 => {
+                SystemBackdropType::Acrylic
+            }
+            v if v == DWM_SYSTEMBACKDROP_TYPE::DWMSBT_TABBEDWINDOW as i32 => {
+                SystemBackdropType::Tabbed
+            }
+            v => SystemBackdropType::Raw(v),
+        })
+    }
+
+    pub fn extend_frame_into_client_area(hwnd: HWND, margins: Margins) -> Result<(), Error> {
+        extend_frame(hwnd, margins)?;
+        let dpi = window_dpi(hwnd);
+        let explicit = match margins {
+            Margins::Explicit {
+                left: 0,
+                right: 0,
+                top: 0,
+                bottom: 0,
             }
+            | Margins::Sheet => None,
+            margins => Some((margins, dpi)),
+        };
+        // only keep state for windows which have explicit margins to scale
+        let known = window_states().contains_key(&hwnd);
+        if explicit.is_some() || known {
+            update_window_state(hwnd, |state| state.frame_margins = explicit)?;
         }
-    } else {
-        None
+        Ok(())
     }
-}
 
-#[repr(C)]
-struct ACCENT_POLICY {
-    AccentState: u32,
-    AccentFlags: u32,
-    GradientColor: u32,
-    AnimationId: u32,
-}
+    fn extend_frame(hwnd: HWND, margins: Margins) -> Result<(), Error> {
+        let margins = match margins {
+            Margins::Sheet => MARGINS {
+                cxLeftWidth: -1,
+                cxRightWidth: -1,
+                cyTopHeight: -1,
+                cyBottomHeight: -1,
+            },
+            Margins::Explicit {
+                left,
+                right,
+                top,
+                bottom,
+            } => MARGINS {
+                cxLeftWidth: left,
+                cxRightWidth: right,
+                cyTopHeight: top,
+                cyBottomHeight: bottom,
+            },
+        };
+        let hr = unsafe { DwmExtendFrameIntoClientArea(hwnd, &margins) };
+        if hr < 0 {
+            return Err(Error::PlatformError {
+                call: "DwmExtendFrameIntoClientArea",
+                code: hr,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn get_visible_frame_border_thickness(hwnd: HWND) -> Result<u32, Error> {
+        if !is_win11() {
+            return Err(Error::UnsupportedPlatformVersion(
+                "\"get_visible_frame_border_thickness()\" is only available on Windows 11.",
+            ));
+        }
+        dwm_get_window_attribute(hwnd, DWMWA_VISIBLE_FRAME_BORDER_THICKNESS)
+    }
 
-type WINDOWCOMPOSITIONATTRIB = u32;
+    pub fn set_dwm_window_attribute(
+        hwnd: HWND,
+        attribute: u32,
+        value: DwmAttributeValue,
+    ) -> Result<(), Error> {
+        let attribute = attribute as DWMWINDOWATTRIBUTE;
+        match value {
+            DwmAttributeValue::Bool(value) => {
+                dwm_set_window_attribute(hwnd, attribute, &BOOL::from(value))
+            }
+            DwmAttributeValue::U32(value) => dwm_set_window_attribute(hwnd, attribute, &value),
+            DwmAttributeValue::Color(color) => {
+                dwm_set_window_attribute(hwnd, attribute, &color_to_colorref(color))
+            }
+        }
+    }
 
-#[repr(C)]
-struct WINDOWCOMPOSITIONATTRIBDATA {
-    Attrib: WINDOWCOMPOSITIONATTRIB,
-    pvData: *mut c_void,
-    cbData: usize,
-}
+    /// Effects and backdrops only apply to top-level windows, DWM silently ignores them on child windows.
+    fn is_child_window(hwnd: HWND) -> bool {
+        is_child_style(unsafe { GetWindowLongW(hwnd, GWL_STYLE) } as u32)
+    }
 
-#[derive(PartialEq)]
-#[repr(C)]
-enum ACCENT_STATE {
-    ACCENT_DISABLED = 0,
-    ACCENT_ENABLE_BLURBEHIND = 3,
-    ACCENT_ENABLE_ACRYLICBLURBEHIND = 4,
-}
+    /// Whether the window `style` makes a child window, which DWM draws no effect behind.
+    fn is_child_style(style: u32) -> bool {
+        style & WS_CHILD != 0
+    }
 
-unsafe fn SetWindowCompositionAttribute(
-    hwnd: HWND,
-    accent_state: ACCENT_STATE,
-    color: Option<Color>,
-) {
-    type SetWindowCompositionAttribute =
-        unsafe extern "system" fn(HWND, *mut WINDOWCOMPOSITIONATTRIBDATA) -> BOOL;
+    fn ensure_top_level(hwnd: HWND) -> Result<(), Error> {
+        if is_child_window(hwnd) {
+            return Err(Error::NotTopLevelWindow(
+                "Effects can only be applied to top-level windows, the window has the WS_CHILD style.",
+            ));
+        }
+        Ok(())
+    }
 
-    if let Some(set_window_composition_attribute) =
-        get_function!("user32.dll", SetWindowCompositionAttribute)
-    {
-        let mut color = color.unwrap_or_default();
+    fn is_layered(hwnd: HWND) -> bool {
+        // extended styles fit in 32 bits, and `GetWindowLongPtrW` doesn't exist on 32-bit targets
+        let ex_style = unsafe { GetWindowLongW(hwnd, GWL_EXSTYLE) } as u32;
+        ex_style & WS_EX_LAYERED != 0
+    }
 
-        let is_acrylic = accent_state == ACCENT_STATE::ACCENT_ENABLE_ACRYLICBLURBEHIND;
-        if is_acrylic && color.3 == 0 {
-            // acrylic doesn't like to have 0 alpha
-            color.3 = 1;
+    /// Windows rendered with DirectComposition often have no redirection surface,
+    /// which the accent policy effects are blended with.
+    fn has_no_redirection_bitmap(hwnd: HWND) -> bool {
+        let ex_style = unsafe { GetWindowLongW(hwnd, GWL_EXSTYLE) } as u32;
+        ex_style & WS_EX_NOREDIRECTIONBITMAP != 0
+    }
+
+    // `HWND` is an `isize` and must be able to hold any handle without truncation,
+    // on every target including 32-bit x86 and ARM64.
+    const _: [(); std::mem::size_of::<*mut c_void>()] = [(); std::mem::size_of::<HWND>()];
+
+    /// Converts the handle stored in a [`raw_window_handle::Win32WindowHandle`] into a `HWND`,
+    /// this is the only place a window pointer is turned into a `HWND`.
+    pub fn to_hwnd(hwnd: *mut c_void) -> HWND {
+        hwnd as HWND
+    }
+
+    /// Runs `f` on a raw window handle that may belong to another process,
+    /// checking the handle first and mapping the errors of foreign windows to their own variants.
+    pub fn with_window_handle<T>(
+        hwnd: HWND,
+        f: impl FnOnce(HWND) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        if unsafe { IsWindow(hwnd) } == 0 {
+            return Err(Error::InvalidWindowHandle(
+                "The given HWND doesn't identify an existing window.",
+            ));
         }
+        f(hwnd).map_err(|error| match error {
+            Error::PlatformError { code, .. }
+                if code == ERROR_ACCESS_DENIED as i32 || code == E_ACCESSDENIED =>
+            {
+                Error::AccessDenied(
+                    "The window belongs to a process that doesn't allow changing it, like an elevated process.",
+                )
+            }
+            Error::PlatformError { code, .. }
+                if code == ERROR_INVALID_WINDOW_HANDLE as i32
+                    || code == HRESULT_FROM_WIN32(ERROR_INVALID_WINDOW_HANDLE) =>
+            {
+                Error::InvalidWindowHandle("The window was destroyed while applying the effect.")
+            }
+            error => error,
+        })
+    }
+
+    /// <https://learn.microsoft.com/en-us/windows/win32/api/winerror/nf-winerror-hresult_from_win32>
+    const fn HRESULT_FROM_WIN32(code: u32) -> i32 {
+        ((code & 0x0000_FFFF) | 0x8007_0000) as i32
+    }
 
-        let mut policy = ACCENT_POLICY {
-            AccentState: accent_state as _,
-            AccentFlags: if is_acrylic { 0 } else { 2 },
-            GradientColor: (color.0 as u32)
-                | (color.1 as u32) << 8
-                | (color.2 as u32) << 16
-                | (color.3 as u32) << 24,
-            AnimationId: 0,
+    pub fn apply_blur_with_system_tint(hwnd: HWND, alpha: u8) -> Result<(), Error> {
+        let (r, g, b, _) = system_accent_color()?;
+        let options = EffectOptions {
+            color: Some((r, g, b, alpha)),
+            ..Default::default()
         };
+        apply_blur(hwnd, options).map(|_| ())
+    }
 
-        let mut data = WINDOWCOMPOSITIONATTRIBDATA {
-            Attrib: 0x13,
-            pvData: &mut policy as *mut _ as _,
-            cbData: std::mem::size_of_val(&policy),
+    pub fn apply_acrylic_with_system_tint(hwnd: HWND, alpha: u8) -> Result<(), Error> {
+        let (r, g, b, _) = system_accent_color()?;
+        let options = EffectOptions {
+            color: Some((r, g, b, alpha)),
+            ..Default::default()
         };
+        apply_acrylic(hwnd, options).map(|_| ())
+    }
 
-        set_window_composition_attribute(hwnd, &mut data as *mut _ as _);
+    /// Returns the accent color of the user, it is read on every call since the user can change it at any time.
+    pub fn system_accent_color() -> Result<Color, Error> {
+        match read_registry_dword("Software\\Microsoft\\Windows\\DWM", "AccentColor") {
+            Some(color) => Ok(color_from_abgr(color)),
+            None => colorization_color(),
+        }
     }
-}
 
-const DWMWA_MICA_EFFECT: DWMWINDOWATTRIBUTE = 1029i32;
-const DWMWA_SYSTEMBACKDROP_TYPE: DWMWINDOWATTRIBUTE = 38i32;
+    /// Converts the `0xAABBGGRR` color of the `AccentColor` registry value to an RGBA [`Color`].
+    fn color_from_abgr(color: u32) -> Color {
+        (
+            color as u8,
+            (color >> 8) as u8,
+            (color >> 16) as u8,
+            (color >> 24) as u8,
+        )
+    }
 
-#[allow(unused)]
-#[repr(C)]
-enum DWM_SYSTEMBACKDROP_TYPE {
-    DWMSBT_DISABLE = 1,         // None
-    DWMSBT_MAINWINDOW = 2,      // Mica
-    DWMSBT_TRANSIENTWINDOW = 3, // Acrylic
-    DWMSBT_TABBEDWINDOW = 4,    // Tabbed
-}
+    /// Converts the `0xAARRGGBB` color of `DwmGetColorizationColor` to an RGBA [`Color`].
+    fn color_from_argb(color: u32) -> Color {
+        (
+            (color >> 16) as u8,
+            (color >> 8) as u8,
+            color as u8,
+            (color >> 24) as u8,
+        )
+    }
 
-fn is_win7() -> bool {
-    let v = get_windows_ver().unwrap_or_default();
-    v.0 == 6 && v.1 == 1
-}
+    pub fn accent_color_on_title_bars() -> bool {
+        read_registry_dword("Software\\Microsoft\\Windows\\DWM", "ColorPrevalence") == Some(1)
+    }
 
-fn is_win10_swca() -> bool {
-    let v = get_windows_ver().unwrap_or_default();
-    v.2 >= 17763 && v.2 < 22000
-}
+    /// Returns the current colorization color DWM derives from the user's accent color.
+    fn colorization_color() -> Result<Color, Error> {
+        let mut color = 0u32;
+        let mut opaque_blend = BOOL::default();
+        let hr = unsafe { DwmGetColorizationColor(&mut color, &mut opaque_blend) };
+        if hr < 0 {
+            return Err(Error::PlatformError {
+                call: "DwmGetColorizationColor",
+                code: hr,
+            });
+        }
+        Ok(color_from_argb(color))
+    }
 
-fn is_win11() -> bool {
-    is_at_least_build(22000)
-}
+    pub fn set_acrylic_tint(hwnd: HWND, color: Color) -> Result<(), Error> {
+        if is_win11_dwmsbt() {
+            return Err(Error::UnsupportedPlatformVersion(
+                "\"set_acrylic_tint()\" is not available on Windows 11 22H2 or newer, where acrylic uses the system backdrop.",
+            ));
+        }
+        let options = match window_states().get(&hwnd) {
+            Some(state) if state.effect == Some(Effect::Acrylic) => EffectOptions {
+                color: Some(color),
+                // don't fade in again, the effect is already there
+                animate: false,
+                ..state.options
+            },
+            _ => {
+                return Err(Error::EffectNotApplied(
+                    "\"set_acrylic_tint()\" requires acrylic to be applied to the window with \"apply_acrylic()\" first.",
+                ))
+            }
+        };
+        unsafe {
+            SetWindowCompositionAttribute(hwnd, AccentState::EnableAcrylicBlurBehind, options)?;
+        }
+        update_window_state(hwnd, |state| {
+            state.options.color = Some(color);
+            if let Some(resize_workaround) = &mut state.resize_workaround {
+                resize_workaround.color = Some(color);
+            }
+        })
+    }
 
-fn is_win11_dwmsbt() -> bool {
-    is_at_least_build(22523)
-}
+    pub fn prepare_popup_blur(hwnd: HWND, options: EffectOptions) -> Result<Applied, Error> {
+        let applied = apply_blur(hwnd, options)?;
+        prepare_popup(hwnd)?;
+        Ok(applied)
+    }
+
+    pub fn prepare_popup_acrylic(hwnd: HWND, options: EffectOptions) -> Result<Applied, Error> {
+        let applied = apply_acrylic(hwnd, options)?;
+        prepare_popup(hwnd)?;
+        Ok(applied)
+    }
+
+    fn prepare_popup(hwnd: HWND) -> Result<(), Error> {
+        // popups are usually created again every time they open, don't keep their state around
+        update_window_state(hwnd, |state| state.track_destroy = true)?;
+        // DWM only picks up the accent of a window that wasn't shown yet once its frame changes,
+        // without this the first frame of the popup is drawn black
+        refresh_frame(hwnd)
+    }
+
+    /// Makes the window recalculate its frame without moving, resizing or activating it.
+    fn refresh_frame(hwnd: HWND) -> Result<(), Error> {
+        let flags = SWP_FRAMECHANGED
+            | SWP_NOMOVE
+            | SWP_NOSIZE
+            | SWP_NOZORDER
+            | SWP_NOOWNERZORDER
+            | SWP_NOACTIVATE;
+        if unsafe { SetWindowPos(hwnd, 0, 0, 0, 0, 0, flags) } == 0 {
+            return Err(Error::PlatformError {
+                call: "SetWindowPos",
+                code: unsafe { GetLastError() } as _,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn apply_mica_frameless(hwnd: HWND, dark: Option<bool>) -> Result<(), Error> {
+        if !supports_mica() {
+            return Err(Error::UnsupportedPlatformVersion(
+                "\"apply_mica_frameless()\" is only available on Windows 11.",
+            ));
+        }
+        if let Some(dark) = dark {
+            set_immersive_dark_mode(hwnd, dark)?;
+        }
+        extend_frame_into_client_area(hwnd, Margins::Sheet)?;
+        apply_mica(hwnd)?;
+        // without a frame change DWM keeps drawing the old frame until the window is resized
+        refresh_frame(hwnd)
+    }
+
+    pub fn clear_mica_frameless(hwnd: HWND) -> Result<bool, Error> {
+        if !supports_mica() {
+            return Err(Error::UnsupportedPlatformVersion(
+                "\"clear_mica_frameless()\" is only available on Windows 11.",
+            ));
+        }
+        let cleared = clear_mica(hwnd)?;
+        extend_frame_into_client_area(
+            hwnd,
+            Margins::Explicit {
+                left: 0,
+                right: 0,
+                top: 0,
+                bottom: 0,
+            },
+        )?;
+        refresh_frame(hwnd)?;
+        Ok(cleared)
+    }
+
+    pub fn set_cloaked(hwnd: HWND, cloaked: bool) -> Result<(), Error> {
+        if !supports_cloak() {
+            return Err(Error::UnsupportedPlatformVersion(
+                "\"set_cloaked()\" is only available on Windows 8 and newer.",
+            ));
+        }
+        dwm_set_window_attribute(hwnd, DWMWA_CLOAK, &BOOL::from(cloaked))
+    }
+
+    pub fn apply_mica_cloaked(hwnd: HWND) -> Result<(), Error> {
+        set_cloaked(hwnd, true)?;
+        let result = apply_mica(hwnd).and_then(|_| {
+            // wait for DWM to compose a frame with the backdrop before showing the window
+            let hr = unsafe { DwmFlush() };
+            if hr < 0 {
+                return Err(Error::PlatformError {
+                    call: "DwmFlush",
+                    code: hr,
+                });
+            }
+            Ok(())
+        });
+        // the window must never stay hidden, even if the backdrop couldn't be applied
+        let uncloaked = set_cloaked(hwnd, false);
+        result.and(uncloaked)
+    }
+
+    pub fn set_nc_rendering_policy(hwnd: HWND, enabled: bool) -> Result<(), Error> {
+        dwm_set_window_attribute(
+            hwnd,
+            DWMWA_NCRENDERING_POLICY,
+            &nc_rendering_policy(enabled),
+        )?;
+        let frame_margins = window_states()
+            .get(&hwnd)
+            .and_then(|state| state.frame_margins);
+        match frame_to_extend_again(enabled, frame_margins, window_dpi(hwnd)) {
+            Some(margins) => extend_frame(hwnd, margins),
+            None => Ok(()),
+        }
+    }
+
+    fn nc_rendering_policy(enabled: bool) -> DWMNCRENDERINGPOLICY {
+        if enabled {
+            DWMNCRP_USEWINDOWSTYLE
+        } else {
+            DWMNCRP_DISABLED
+        }
+    }
+
+    /// Returns the frame to extend the window by once the rendering of the non-client area is `enabled` again,
+    /// the explicit margins given to [`extend_frame_into_client_area`] before, scaled to the current `dpi`.
+    ///
+    /// The frame has to be extended after the policy is set, disabling the rendering drops it.
+    fn frame_to_extend_again(
+        enabled: bool,
+        frame_margins: Option<(Margins, u32)>,
+        dpi: u32,
+    ) -> Option<Margins> {
+        match frame_margins {
+            Some((margins, margins_dpi)) if enabled => {
+                Some(scale_margins(margins, margins_dpi, dpi))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn apply_blur_cross_thread(hwnd: HWND, options: EffectOptions) -> Result<Applied, Error> {
+        run_on_window_thread(hwnd, move || apply_blur(hwnd, options))
+    }
+
+    type CrossThreadCall = Box<dyn FnOnce() + Send>;
 
-fn is_at_least_build(build: u32) -> bool {
-    let v = get_windows_ver().unwrap_or_default();
-    v.2 >= build
+    /// Calls waiting to run on the thread of their window, keyed by the id sent with [`CROSS_THREAD_MESSAGE`].
+    static CROSS_THREAD_CALLS: Lazy<Mutex<HashMap<usize, CrossThreadCall>>> =
+        Lazy::new(Default::default);
+
+    /// Locks the waiting calls, also after a panic while they were locked.
+    /// They are used from the hook procedure, where a panic would abort the process.
+    fn cross_thread_calls() -> MutexGuard<'static, HashMap<usize, CrossThreadCall>> {
+        CROSS_THREAD_CALLS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    static CROSS_THREAD_MESSAGE: Lazy<u32> = Lazy::new(|| unsafe {
+        RegisterWindowMessageW(to_wide("WindowVibrancyCrossThreadCall").as_ptr())
+    });
+    const CROSS_THREAD_TIMEOUT_MS: u32 = 5000;
+
+    /// Runs `f` on the thread that created the window, which must be pumping messages.
+    fn run_on_window_thread<T: Send + 'static>(
+        hwnd: HWND,
+        f: impl FnOnce() -> Result<T, Error> + Send + 'static,
+    ) -> Result<T, Error> {
+        let mut process_id = 0;
+        let thread_id = unsafe { GetWindowThreadProcessId(hwnd, &mut process_id) };
+        if thread_id == 0 {
+            return Err(Error::InvalidWindowHandle(
+                "The window handle doesn't identify an existing window.",
+            ));
+        }
+        if thread_id == unsafe { GetCurrentThreadId() } {
+            return f();
+        }
+        if process_id != unsafe { GetCurrentProcessId() } {
+            return Err(Error::WrongThread(
+                "Calls can only be marshaled onto the thread of a window of the current process.",
+            ));
+        }
+
+        static NEXT_ID: AtomicUsize = AtomicUsize::new(1);
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        cross_thread_calls().insert(
+            id,
+            Box::new(move || {
+                let _ = sender.send(f());
+            }),
+        );
+
+        let hook =
+            unsafe { SetWindowsHookExW(WH_CALLWNDPROC, Some(cross_thread_hook), 0, thread_id) };
+        if hook == 0 {
+            cross_thread_calls().remove(&id);
+            return Err(Error::PlatformError {
+                call: "SetWindowsHookExW",
+                code: unsafe { GetLastError() } as _,
+            });
+        }
+        let mut result = 0;
+        let sent = unsafe {
+            SendMessageTimeoutW(
+                hwnd,
+                *CROSS_THREAD_MESSAGE,
+                0,
+                id as _,
+                SMTO_ABORTIFHUNG,
+                CROSS_THREAD_TIMEOUT_MS,
+                &mut result,
+            )
+        };
+        let error = unsafe { GetLastError() };
+        unsafe { UnhookWindowsHookEx(hook) };
+        // a call that didn't run yet must not run after the caller gave up on it
+        cross_thread_calls().remove(&id);
+        match receiver.try_recv() {
+            Ok(result) => result,
+            Err(_) if sent == 0 => Err(Error::PlatformError {
+                call: "SendMessageTimeoutW",
+                code: error as _,
+            }),
+            Err(_) => Err(Error::WrongThread(
+                "The call couldn't be run on the thread of the window.",
+            )),
+        }
+    }
+
+    unsafe extern "system" fn cross_thread_hook(
+        code: i32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        if code == HC_ACTION as i32 {
+            let message = &*(lparam as *const CWPSTRUCT);
+            if message.message == *CROSS_THREAD_MESSAGE {
+                let call = cross_thread_calls().remove(&(message.lParam as usize));
+                if let Some(call) = call {
+                    // the caller gets an error when the call panics, since its sender is dropped then
+                    let _ = std::panic::catch_unwind(std::panic::AssertUnwindSafe(call));
+                }
+            }
+        }
+        CallNextHookEx(0, code, wparam, lparam)
+    }
+
+    pub fn set_blur_enabled(hwnd: HWND, enabled: bool) -> Result<(), Error> {
+        if !is_win7() {
+            return Err(Error::UnsupportedPlatformVersion(
+                "\"set_blur_enabled()\" is only available on Windows 7.",
+            ));
+        }
+        let suspended = match window_states().get(&hwnd) {
+            Some(state) if state.effect == Some(Effect::Blur) => state.suspended,
+            _ => {
+                return Err(Error::EffectNotApplied(
+                    "\"set_blur_enabled()\" requires blur to be applied to the window with \"apply_blur()\" or \"apply_blur_region()\" first.",
+                ))
+            }
+        };
+        // a suspended effect comes back when the window is restored, unless it was disabled meanwhile
+        if !suspended {
+            // only `fEnable` is updated, DWM keeps the blur region
+            let bb = DWM_BLURBEHIND {
+                dwFlags: DWM_BB_ENABLE,
+                fEnable: enabled.into(),
+                hRgnBlur: HRGN::default(),
+                fTransitionOnMaximized: 0,
+            };
+            dwm_enable_blur_behind_window(hwnd, &bb)?;
+        }
+        update_window_state(hwnd, |state| state.disabled = !enabled)
+    }
+
+    fn set_system_backdrop(hwnd: HWND, backdrop_type: SystemBackdropType) -> Result<(), Error> {
+        let value: i32 = match backdrop_type {
+            SystemBackdropType::Auto => DWM_SYSTEMBACKDROP_TYPE::DWMSBT_AUTO as _,
+            SystemBackdropType::None => DWM_SYSTEMBACKDROP_TYPE::DWMSBT_DISABLE as _,
+            SystemBackdropType::Mica => DWM_SYSTEMBACKDROP_TYPE::DWMSBT_MAINWINDOW as _,
+            SystemBackdropType::Acrylic => DWM_SYSTEMBACKDROP_TYPE::DWMSBT_TRANSIENTWINDOW as _,
+            SystemBackdropType::Tabbed => DWM_SYSTEMBACKDROP_TYPE::DWMSBT_TABBEDWINDOW as _,
+            SystemBackdropType::Raw(value) => value,
+        };
+        dwm_set_window_attribute(hwnd, DWMWA_SYSTEMBACKDROP_TYPE, &value)
+    }
+
+    fn dwm_set_window_attribute<T>(
+        hwnd: HWND,
+        attribute: DWMWINDOWATTRIBUTE,
+        value: &T,
+    ) -> Result<(), Error> {
+        let hr = unsafe {
+            DwmSetWindowAttribute(
+                hwnd,
+                attribute,
+                value as *const _ as _,
+                std::mem::size_of::<T>() as _,
+            )
+        };
+        if hr < 0 {
+            return Err(Error::PlatformError {
+                call: "DwmSetWindowAttribute",
+                code: hr,
+            });
+        }
+        Ok(())
+    }
+
+    /// Converts an RGBA [`Color`] to a `COLORREF` (`0x00BBGGRR`), the alpha channel is dropped.
+    fn color_to_colorref(color: Color) -> u32 {
+        color_to_gradient(color) & 0x00FF_FFFF
+    }
+
+    /// Creates a region out of the union of the given (x, y, width, height) rectangles,
+    /// the caller is responsible for deleting it.
+    fn create_region(rects: &[(i32, i32, i32, i32)]) -> Result<HRGN, Error> {
+        unsafe {
+            let region = CreateRectRgn(0, 0, 0, 0);
+            if region == 0 {
+                return Err(Error::PlatformError {
+                    call: "CreateRectRgn",
+                    code: GetLastError() as _,
+                });
+            }
+            for &(x, y, width, height) in rects {
+                let rect = CreateRectRgn(x, y, x + width, y + height);
+                let combined = rect != 0 && CombineRgn(region, region, rect, RGN_OR) != ERROR as _;
+                if rect != 0 {
+                    DeleteObject(rect);
+                }
+                if !combined {
+                    DeleteObject(region);
+                    return Err(Error::PlatformError {
+                        call: "CombineRgn",
+                        code: GetLastError() as _,
+                    });
+                }
+            }
+            Ok(region)
+        }
+    }
+
+    fn dwm_get_window_attribute<T: Default>(
+        hwnd: HWND,
+        attribute: DWMWINDOWATTRIBUTE,
+    ) -> Result<T, Error> {
+        let mut value = T::default();
+        let hr = unsafe {
+            DwmGetWindowAttribute(
+                hwnd,
+                attribute,
+                &mut value as *mut _ as _,
+                std::mem::size_of::<T>() as _,
+            )
+        };
+        if hr < 0 {
+            return Err(Error::PlatformError {
+                call: "DwmGetWindowAttribute",
+                code: hr,
+            });
+        }
+        Ok(value)
+    }
+
+    fn dwm_enable_blur_behind_window(hwnd: HWND, bb: &DWM_BLURBEHIND) -> Result<(), Error> {
+        let hr = unsafe { DwmEnableBlurBehindWindow(hwnd, bb) };
+        if hr < 0 {
+            return Err(Error::PlatformError {
+                call: "DwmEnableBlurBehindWindow",
+                code: hr,
+            });
+        }
+        Ok(())
+    }
+
+    /// An effect applied by the crate.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Effect {
+        Blur,
+        Transparent,
+        Acrylic,
+        HostBackdropAccent,
+        Mica,
+        Tabbed,
+        SystemBackdrop(SystemBackdropType),
+    }
+
+    /// State the crate keeps for a window.
+    #[derive(Default)]
+    struct WindowState {
+        /// The effect last applied to the window.
+        effect: Option<Effect>,
+        /// Acrylic to swap for blur during the modal size/move loop.
+        resize_workaround: Option<EffectOptions>,
+        /// The options the effect was applied with.
+        options: EffectOptions,
+        /// The regions blur was applied to with `apply_blur_region()`, empty for the whole window.
+        blur_region: Vec<(i32, i32, i32, i32)>,
+        /// The DPI the blur region was given for.
+        blur_region_dpi: u32,
+        /// The explicit margins the frame was extended by and the DPI they were given for,
+        /// see [`extend_frame_into_client_area`].
+        frame_margins: Option<(Margins, u32)>,
+        /// Whether to scale the frame margins when the DPI of the window changes,
+        /// see [`install_dpi_rescale_hook`].
+        dpi_rescale_hook: bool,
+        /// Whether to re-apply the effect when the system drops it, see [`install_reapply_hook`].
+        reapply_hook: bool,
+        /// Whether the effect is disabled because the window is maximized,
+        /// see [`EffectOptions::suspend_when_maximized`].
+        suspended: bool,
+        /// Whether the effect is disabled with [`set_blur_enabled`].
+        disabled: bool,
+        /// Whether to subclass the window only to drop its state when it is destroyed,
+        /// for popups that are created again every time they open.
+        track_destroy: bool,
+        /// The state of the window before the crate applied an effect to it, restored when the effect is cleared.
+        snapshot: Option<Snapshot>,
+        /// Whether to update the immersive dark mode when the system theme changes,
+        /// see [`follow_system_theme`].
+        follow_system_theme: bool,
+        /// Whether [`subclass_proc`] is installed on the window.
+        subclassed: bool,
+    }
+
+    /// The accent policy and system backdrop set on a window, by the application or another library,
+    /// before the crate applied an effect to it.
+    #[derive(Clone, Copy)]
+    struct Snapshot {
+        accent_policy: Option<ACCENT_POLICY>,
+        system_backdrop: Option<i32>,
+    }
+
+    impl WindowState {
+        fn record(&mut self, effect: Option<Effect>, options: EffectOptions) {
+            self.effect = effect;
+            self.options = options;
+            self.blur_region.clear();
+            self.suspended = false;
+            self.disabled = false;
+            if effect.is_none() {
+                self.snapshot = None;
+            }
+        }
+
+        /// Forgets the effect if it is `effect`, returns whether it was.
+        fn forget(&mut self, effect: Effect) -> bool {
+            if self.effect != Some(effect) {
+                return false;
+            }
+            self.record(None, EffectOptions::default());
+            true
+        }
+
+        fn needs_subclass(&self) -> bool {
+            self.resize_workaround.is_some()
+                || self.reapply_hook
+                || self.follow_system_theme
+                || self.dpi_rescale_hook
+                || self.track_destroy
+                || (self.effect.is_some() && self.options.suspend_when_maximized)
+        }
+
+        fn is_empty(&self) -> bool {
+            self.effect.is_none()
+                && self.snapshot.is_none()
+                && self.frame_margins.is_none()
+                && !self.needs_subclass()
+        }
+    }
+
+    static WINDOW_STATES: Lazy<Mutex<HashMap<HWND, WindowState>>> = Lazy::new(Default::default);
+
+    /// Locks the window states, also after a panic while they were locked.
+    /// They are used from the subclass procedure, where a panic would abort the process.
+    fn window_states() -> MutexGuard<'static, HashMap<HWND, WindowState>> {
+        WINDOW_STATES.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    const SUBCLASS_ID: usize = 0x5649_4252;
+
+    /// Updates the state of the window, subclassing it or removing the subclass as needed.
+    /// Calling this repeatedly never installs more than one subclass on the same window.
+    fn update_window_state(hwnd: HWND, f: impl FnOnce(&mut WindowState)) -> Result<(), Error> {
+        let mut states = window_states();
+        if !states.contains_key(&hwnd) {
+            // windows that weren't subclassed don't tell us when they are destroyed,
+            // drop their state here so it doesn't grow forever
+            states.retain(|&hwnd, _| unsafe { IsWindow(hwnd) } != 0);
+        }
+        let state = states.entry(hwnd).or_default();
+        f(state);
+        let mut result = Ok(());
+        if state.needs_subclass() && !state.subclassed {
+            if unsafe { SetWindowSubclass(hwnd, Some(subclass_proc), SUBCLASS_ID, 0) } != 0 {
+                state.subclassed = true;
+            } else {
+                result = Err(Error::PlatformError {
+                    call: "SetWindowSubclass",
+                    code: unsafe { GetLastError() } as _,
+                });
+            }
+        } else if !state.needs_subclass() && state.subclassed {
+            unsafe { RemoveWindowSubclass(hwnd, Some(subclass_proc), SUBCLASS_ID) };
+            state.subclassed = false;
+        }
+        if state.is_empty() {
+            states.remove(&hwnd);
+        }
+        result
+    }
+
+    fn current_effect(hwnd: HWND) -> Option<Effect> {
+        window_states().get(&hwnd).and_then(|state| state.effect)
+    }
+
+    /// Clears the effect applied by the crate to the window, if any.
+    fn clear_effect(hwnd: HWND) -> Result<(), Error> {
+        match current_effect(hwnd) {
+            Some(Effect::Blur) => clear_blur(hwnd).map(|_| ()),
+            Some(Effect::Transparent) => clear_transparent(hwnd).map(|_| ()),
+            Some(Effect::Acrylic) => clear_acrylic(hwnd).map(|_| ()),
+            Some(Effect::HostBackdropAccent) => clear_host_backdrop_accent(hwnd).map(|_| ()),
+            Some(Effect::Mica) => clear_mica(hwnd).map(|_| ()),
+            Some(Effect::Tabbed) => clear_tabbed(hwnd).map(|_| ()),
+            Some(Effect::SystemBackdrop(_)) => {
+                restore_system_backdrop(hwnd)?;
+                record_effect(hwnd, None)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Clears the effect applied by the crate to the window if it is not `effect`,
+    /// so that switching effects doesn't leave the previous one active underneath.
+    fn switch_effect(hwnd: HWND, effect: Effect) -> Result<(), Error> {
+        match effect_to_clear(current_effect(hwnd), effect) {
+            Some(_) => clear_effect(hwnd),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the `current` effect if applying `effect` has to clear it first.
+    fn effect_to_clear(current: Option<Effect>, effect: Effect) -> Option<Effect> {
+        current.filter(|&current| current != effect)
+    }
+
+    fn record_effect(hwnd: HWND, effect: Option<Effect>) -> Result<(), Error> {
+        update_window_state(hwnd, |state| state.record(effect, EffectOptions::default()))
+    }
+
+    fn record_effect_with_options(
+        hwnd: HWND,
+        effect: Effect,
+        options: EffectOptions,
+    ) -> Result<(), Error> {
+        update_window_state(hwnd, |state| state.record(Some(effect), options))
+    }
+
+    /// Forgets the effect applied to the window if it is `effect`.
+    /// Returns whether it was.
+    fn forget_effect(hwnd: HWND, effect: Effect) -> Result<bool, Error> {
+        let mut forgotten = false;
+        update_window_state(hwnd, |state| forgotten = state.forget(effect))?;
+        Ok(forgotten)
+    }
+
+    /// Captures the state of the window before the crate applies an effect to it,
+    /// unless an effect of the crate is already applied and the snapshot was taken before that one.
+    fn capture_snapshot(hwnd: HWND) -> Result<(), Error> {
+        let needed = window_states().get(&hwnd).map_or(true, |state| {
+            state.effect.is_none() && state.snapshot.is_none()
+        });
+        if !needed {
+            return Ok(());
+        }
+        let snapshot = Snapshot {
+            accent_policy: unsafe { GetWindowCompositionAttribute(hwnd) }.ok(),
+            system_backdrop: if is_win11_dwmsbt() {
+                dwm_get_window_attribute(hwnd, DWMWA_SYSTEMBACKDROP_TYPE).ok()
+            } else {
+                None
+            },
+        };
+        update_window_state(hwnd, |state| state.snapshot = Some(snapshot))
+    }
+
+    fn snapshot(hwnd: HWND) -> Option<Snapshot> {
+        window_states().get(&hwnd).and_then(|state| state.snapshot)
+    }
+
+    /// Restores the accent policy captured by [`capture_snapshot`], or disables the accent.
+    fn restore_accent_policy(hwnd: HWND) -> Result<(), Error> {
+        let policy = snapshot(hwnd)
+            .and_then(|snapshot| snapshot.accent_policy)
+            .unwrap_or_else(|| AccentPolicy::new(AccentState::Disabled).into());
+        unsafe { set_raw_accent_policy(hwnd, policy) }
+    }
+
+    /// Restores the system backdrop captured by [`capture_snapshot`], or lets DWM decide it.
+    fn restore_system_backdrop(hwnd: HWND) -> Result<(), Error> {
+        match snapshot(hwnd).and_then(|snapshot| snapshot.system_backdrop) {
+            Some(backdrop_type) => {
+                dwm_set_window_attribute(hwnd, DWMWA_SYSTEMBACKDROP_TYPE, &backdrop_type)
+            }
+            None => set_system_backdrop(hwnd, SystemBackdropType::Auto),
+        }
+    }
+
+    pub fn install_dpi_rescale_hook(hwnd: HWND) -> Result<(), Error> {
+        update_window_state(hwnd, |state| state.dpi_rescale_hook = true)
+    }
+
+    pub fn remove_dpi_rescale_hook(hwnd: HWND) -> Result<(), Error> {
+        update_window_state(hwnd, |state| state.dpi_rescale_hook = false)
+    }
+
+    /// Scales the frame margins of the window to its new DPI.
+    ///
+    /// Blur regions are only supported on Windows 7, which never sends `WM_DPICHANGED`: its DPI is
+    /// the same for every monitor and only changes after signing in again.
+    fn rescale_for_dpi(hwnd: HWND, new_dpi: u32) -> Result<(), Error> {
+        // copy the state out so the lock isn't held while calling into DWM
+        let frame_margins = match window_states().get(&hwnd) {
+            Some(state) if state.dpi_rescale_hook => state.frame_margins,
+            _ => return Ok(()),
+        };
+        if let Some((margins, dpi)) = frame_margins {
+            extend_frame(hwnd, scale_margins(margins, dpi, new_dpi))?;
+        }
+        Ok(())
+    }
+
+    /// Scales explicit margins given for `from` DPI to `to` DPI, [`Margins::Sheet`] stays the same.
+    fn scale_margins(margins: Margins, from: u32, to: u32) -> Margins {
+        match margins {
+            Margins::Explicit {
+                left,
+                right,
+                top,
+                bottom,
+            } => Margins::Explicit {
+                left: scale_for_dpi(left, from, to),
+                right: scale_for_dpi(right, from, to),
+                top: scale_for_dpi(top, from, to),
+                bottom: scale_for_dpi(bottom, from, to),
+            },
+            Margins::Sheet => Margins::Sheet,
+        }
+    }
+
+    /// Scales a length in pixels given for `from` DPI to `to` DPI.
+    fn scale_for_dpi(value: i32, from: u32, to: u32) -> i32 {
+        if from == to || from == 0 {
+            return value;
+        }
+        (value as f64 * to as f64 / from as f64).round() as i32
+    }
+
+    /// Scales rectangles given for `from` DPI to `to` DPI.
+    fn scale_rects(
+        rects: &[(i32, i32, i32, i32)],
+        from: u32,
+        to: u32,
+    ) -> Vec<(i32, i32, i32, i32)> {
+        rects
+            .iter()
+            .map(|&(x, y, width, height)| {
+                (
+                    scale_for_dpi(x, from, to),
+                    scale_for_dpi(y, from, to),
+                    scale_for_dpi(width, from, to),
+                    scale_for_dpi(height, from, to),
+                )
+            })
+            .collect()
+    }
+
+    pub fn install_reapply_hook(hwnd: HWND) -> Result<(), Error> {
+        update_window_state(hwnd, |state| state.reapply_hook = true)
+    }
+
+    pub fn remove_reapply_hook(hwnd: HWND) -> Result<(), Error> {
+        update_window_state(hwnd, |state| state.reapply_hook = false)
+    }
+
+    /// Applies the effect recorded for the window again, after the system dropped it.
+    fn reapply_effect(hwnd: HWND) -> Result<(), Error> {
+        // copy the state out so the lock isn't held while calling into DWM
+        let (effect, options, blur_region, blur_region_dpi, resize_workaround) =
+            match window_states().get(&hwnd) {
+                // the effect is brought back when the window is restored
+                Some(state) if state.suspended => return Ok(()),
+                // the effect is brought back by `set_blur_enabled()`
+                Some(state) if state.disabled => return Ok(()),
+                Some(state) => (
+                    state.effect,
+                    EffectOptions {
+                        animate: false,
+                        ..state.options
+                    },
+                    state.blur_region.clone(),
+                    state.blur_region_dpi,
+                    state.resize_workaround,
+                ),
+                None => return Ok(()),
+            };
+        match effect {
+            Some(Effect::Blur) if !blur_region.is_empty() => {
+                apply_blur_region_at(hwnd, &blur_region, blur_region_dpi)
+            }
+            Some(Effect::Blur) => apply_blur(hwnd, options).map(|_| ()),
+            Some(Effect::Transparent) => apply_transparent(hwnd, options.color),
+            Some(Effect::Acrylic) => {
+                apply_acrylic(hwnd, options)?;
+                update_window_state(hwnd, |state| state.resize_workaround = resize_workaround)
+            }
+            Some(Effect::HostBackdropAccent) => apply_host_backdrop_accent(hwnd, options.color),
+            Some(Effect::Mica) => apply_mica(hwnd),
+            Some(Effect::Tabbed) => apply_tabbed(hwnd),
+            Some(Effect::SystemBackdrop(backdrop_type)) => {
+                apply_system_backdrop(hwnd, backdrop_type)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Disables the effect applied to the window while it is maximized or fullscreen,
+    /// if it was applied with [`EffectOptions::suspend_when_maximized`].
+    ///
+    /// Only blur and acrylic are recorded with options, other effects are never suspended.
+    fn suspend_if_maximized(hwnd: HWND) -> Result<(), Error> {
+        let effect = match window_states().get(&hwnd) {
+            Some(state)
+                if state.options.suspend_when_maximized && !state.suspended && !state.disabled =>
+            {
+                state.effect
+            }
+            _ => None,
+        };
+        let effect = match effect {
+            Some(effect) if is_maximized_or_fullscreen(hwnd) => effect,
+            _ => return Ok(()),
+        };
+        match effect {
+            Effect::Blur if is_win7() => {
+                let bb = DWM_BLURBEHIND {
+                    dwFlags: DWM_BB_ENABLE,
+                    fEnable: false.into(),
+                    hRgnBlur: HRGN::default(),
+                    fTransitionOnMaximized: 0,
+                };
+                dwm_enable_blur_behind_window(hwnd, &bb)?;
+            }
+            Effect::Acrylic if is_win11_dwmsbt() => {
+                set_system_backdrop(hwnd, SystemBackdropType::None)?;
+            }
+            _ => unsafe {
+                SetWindowCompositionAttribute(
+                    hwnd,
+                    AccentState::Disabled,
+                    EffectOptions::default(),
+                )?;
+            },
+        }
+        update_window_state(hwnd, |state| state.suspended = true)
+    }
+
+    /// Brings back the effect disabled by [`suspend_if_maximized`] once the window is restored.
+    fn resume_if_restored(hwnd: HWND) -> Result<(), Error> {
+        let suspended = window_states()
+            .get(&hwnd)
+            .map_or(false, |state| state.suspended);
+        if !suspended || is_maximized_or_fullscreen(hwnd) {
+            return Ok(());
+        }
+        update_window_state(hwnd, |state| state.suspended = false)?;
+        reapply_effect(hwnd)
+    }
+
+    fn is_maximized_or_fullscreen(hwnd: HWND) -> bool {
+        unsafe {
+            if IsZoomed(hwnd) != 0 {
+                return true;
+            }
+            let mut rect: RECT = std::mem::zeroed();
+            let mut info: MONITORINFO = std::mem::zeroed();
+            info.cbSize = std::mem::size_of::<MONITORINFO>() as _;
+            let monitor = MonitorFromWindow(hwnd, MONITOR_DEFAULTTONEAREST);
+            if GetWindowRect(hwnd, &mut rect) == 0 || GetMonitorInfoW(monitor, &mut info) == 0 {
+                return false;
+            }
+            let monitor = info.rcMonitor;
+            rect.left <= monitor.left
+                && rect.top <= monitor.top
+                && rect.right >= monitor.right
+                && rect.bottom >= monitor.bottom
+        }
+    }
+
+    fn has_reapply_hook(hwnd: HWND) -> bool {
+        window_states()
+            .get(&hwnd)
+            .map_or(false, |state| state.reapply_hook)
+    }
+
+    /// Returns whether `lparam` of `WM_SETTINGCHANGE` names the `name` setting.
+    unsafe fn is_setting(lparam: LPARAM, name: &str) -> bool {
+        if lparam == 0 {
+            return false;
+        }
+        let setting = lparam as *const u16;
+        let mut len = 0;
+        while *setting.add(len) != 0 {
+            len += 1;
+        }
+        std::slice::from_raw_parts(setting, len)
+            .iter()
+            .copied()
+            .eq(name.encode_utf16())
+    }
+
+    unsafe extern "system" fn subclass_proc(
+        hwnd: HWND,
+        msg: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+        _: usize,
+        _: usize,
+    ) -> LRESULT {
+        match msg {
+            WM_ENTERSIZEMOVE | WM_EXITSIZEMOVE => {
+                // copy the options out so the lock isn't held while calling into DWM
+                let resize_workaround = window_states()
+                    .get(&hwnd)
+                    .filter(|state| !state.suspended)
+                    .and_then(|state| state.resize_workaround);
+                if let Some(options) = resize_workaround {
+                    let accent_state = if msg == WM_ENTERSIZEMOVE {
+                        AccentState::EnableBlurBehind
+                    } else {
+                        AccentState::EnableAcrylicBlurBehind
+                    };
+                    let options = EffectOptions {
+                        animate: false,
+                        ..options
+                    };
+                    let _ = SetWindowCompositionAttribute(hwnd, accent_state, options);
+                }
+            }
+            WM_SIZE => match u32::try_from(wparam) {
+                Ok(SIZE_MAXIMIZED) => {
+                    let _ = suspend_if_maximized(hwnd);
+                }
+                // a borderless window can also be resized to cover the whole monitor
+                Ok(SIZE_RESTORED) => {
+                    let _ = resume_if_restored(hwnd);
+                    let _ = suspend_if_maximized(hwnd);
+                }
+                _ => {}
+            },
+            WM_DPICHANGED => {
+                let _ = rescale_for_dpi(hwnd, (wparam & 0xFFFF) as u32);
+            }
+            WM_DWMCOMPOSITIONCHANGED | WM_THEMECHANGED if has_reapply_hook(hwnd) => {
+                let _ = reapply_effect(hwnd);
+            }
+            // sent when the theme or the transparency effects setting changes
+            WM_SETTINGCHANGE if is_setting(lparam, "ImmersiveColorSet") => {
+                let follow_system_theme = window_states()
+                    .get(&hwnd)
+                    .map_or(false, |state| state.follow_system_theme);
+                if follow_system_theme {
+                    let _ = set_immersive_dark_mode(hwnd, dark_mode_enabled());
+                }
+                if has_reapply_hook(hwnd) {
+                    let _ = reapply_effect(hwnd);
+                }
+            }
+            WM_NCDESTROY => {
+                window_states().remove(&hwnd);
+                RemoveWindowSubclass(hwnd, Some(subclass_proc), SUBCLASS_ID);
+            }
+            _ => {}
+        }
+        DefSubclassProc(hwnd, msg, wparam, lparam)
+    }
+
+    fn get_function_impl(library: &str, function: &str) -> FARPROC {
+        assert_eq!(library.chars().last(), Some('\0'));
+        assert_eq!(function.chars().last(), Some('\0'));
+
+        let module = unsafe { LoadLibraryA(library.as_ptr()) };
+        if module == 0 {
+            return None;
+        }
+        unsafe { GetProcAddress(module, function.as_ptr()) }
+    }
+
+    macro_rules! get_function {
+        ($lib:expr, $func:ident) => {
+            get_function_impl(concat!($lib, '\0'), concat!(stringify!($func), '\0'))
+                .map(|f| std::mem::transmute::<unsafe extern "system" fn() -> isize, $func>(f))
+        };
+    }
+
+    /// Returns the function cached in `cell`, calling `load` to resolve it on first use only.
+    /// A missing export is cached too, so it isn't looked up again on every call.
+    fn cached_function<F: Copy>(
+        cell: &OnceCell<Option<F>>,
+        load: impl FnOnce() -> Option<F>,
+    ) -> Option<F> {
+        *cell.get_or_init(load)
+    }
+
+    /// Returns the DPI of the window, or the default of 96 before Windows 10 v1607
+    /// where windows can't have a DPI of their own.
+    fn window_dpi(hwnd: HWND) -> u32 {
+        type GetDpiForWindow = unsafe extern "system" fn(HWND) -> u32;
+        static GET_DPI_FOR_WINDOW: Lazy<Option<GetDpiForWindow>> =
+            Lazy::new(|| unsafe { get_function!("user32.dll", GetDpiForWindow) });
+
+        match *GET_DPI_FOR_WINDOW {
+            Some(get_dpi_for_window) => match unsafe { get_dpi_for_window(hwnd) } {
+                0 => USER_DEFAULT_SCREEN_DPI,
+                dpi => dpi,
+            },
+            None => USER_DEFAULT_SCREEN_DPI,
+        }
+    }
+
+    /// Build number set by [`override_build_number`], `0` means no override.
+    static BUILD_NUMBER_OVERRIDE: AtomicU32 = AtomicU32::new(0);
+
+    pub fn override_build_number(build: Option<u32>) {
+        BUILD_NUMBER_OVERRIDE.store(build.unwrap_or(0), Ordering::SeqCst);
+    }
+
+    pub fn build_number() -> Option<u32> {
+        get_windows_ver().map(|v| v.2)
+    }
+
+    /// Reads the "Transparency effects" setting of the user, which is also turned off by battery saver.
+    /// The setting only exists on Windows 10 and newer, transparency is always enabled before.
+    pub fn transparency_effects_enabled() -> bool {
+        let enabled = read_registry_dword(
+            "Software\\Microsoft\\Windows\\CurrentVersion\\Themes\\Personalize",
+            "EnableTransparency",
+        ) != Some(0);
+        enabled && !is_battery_saver_on()
+    }
+
+    pub fn high_contrast_enabled() -> bool {
+        let mut high_contrast: HIGHCONTRASTW = unsafe { std::mem::zeroed() };
+        high_contrast.cbSize = std::mem::size_of::<HIGHCONTRASTW>() as _;
+        let ok = unsafe {
+            SystemParametersInfoW(
+                SPI_GETHIGHCONTRAST,
+                high_contrast.cbSize,
+                &mut high_contrast as *mut _ as _,
+                0,
+            )
+        };
+        ok != 0 && high_contrast.dwFlags & HCF_HIGHCONTRASTON != 0
+    }
+
+    fn is_battery_saver_on() -> bool {
+        let mut status: SYSTEM_POWER_STATUS = unsafe { std::mem::zeroed() };
+        unsafe { GetSystemPowerStatus(&mut status) != 0 && status.SystemStatusFlag == 1 }
+    }
+
+    /// Reads a `REG_DWORD` value of a key under `HKEY_CURRENT_USER`.
+    fn read_registry_dword(key: &str, value: &str) -> Option<u32> {
+        let key = to_wide(key);
+        let value = to_wide(value);
+        let mut data = 0u32;
+        let mut size = std::mem::size_of::<u32>() as u32;
+        let status = unsafe {
+            RegGetValueW(
+                HKEY_CURRENT_USER,
+                key.as_ptr(),
+                value.as_ptr(),
+                RRF_RT_REG_DWORD,
+                std::ptr::null_mut(),
+                &mut data as *mut _ as _,
+                &mut size,
+            )
+        };
+        (status == ERROR_SUCCESS).then(|| data)
+    }
+
+    /// Converts a string to a nul-terminated wide string.
+    fn to_wide(string: &str) -> Vec<u16> {
+        string.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    /// Returns a tuple of (major, minor, buildnumber), queried once and cached for the lifetime of the process.
+    fn get_windows_ver() -> Option<(u32, u32, u32)> {
+        static WINDOWS_VERSION: Lazy<Option<(u32, u32, u32)>> = Lazy::new(query_windows_ver);
+        match BUILD_NUMBER_OVERRIDE.load(Ordering::SeqCst) {
+            0 => *WINDOWS_VERSION,
+            build => Some(version_from_build(build)),
+        }
+    }
+
+    /// Returns the (major, minor, buildnumber) tuple of the Windows release a build number belongs to.
+    fn version_from_build(build: u32) -> (u32, u32, u32) {
+        match build {
+            0..=7599 => (6, 0, build),
+            7600..=9199 => (6, 1, build),
+            9200..=9599 => (6, 2, build),
+            9600..=10239 => (6, 3, build),
+            _ => (10, 0, build),
+        }
+    }
+
+    fn query_windows_ver() -> Option<(u32, u32, u32)> {
+        type RtlGetVersion = unsafe extern "system" fn(*mut OSVERSIONINFOW) -> i32;
+        let handle = unsafe { get_function!("ntdll.dll", RtlGetVersion) };
+        if let Some(rtl_get_version) = handle {
+            unsafe {
+                let mut vi = OSVERSIONINFOW {
+                    dwOSVersionInfoSize: 0,
+                    dwMajorVersion: 0,
+                    dwMinorVersion: 0,
+                    dwBuildNumber: 0,
+                    dwPlatformId: 0,
+                    szCSDVersion: [0; 128],
+                };
+
+                let status = (rtl_get_version)(&mut vi as _);
+
+                if status >= 0 {
+                    Some((vi.dwMajorVersion, vi.dwMinorVersion, vi.dwBuildNumber))
+                } else {
+                    None
+                }
+            }
+        } else {
+            None
+        }
+    }
+
+    #[derive(Clone, Copy)]
+    #[repr(C)]
+    struct ACCENT_POLICY {
+        AccentState: u32,
+        AccentFlags: u32,
+        GradientColor: u32,
+        AnimationId: u32,
+    }
+
+    type WINDOWCOMPOSITIONATTRIB = u32;
+
+    #[repr(C)]
+    struct WINDOWCOMPOSITIONATTRIBDATA {
+        Attrib: WINDOWCOMPOSITIONATTRIB,
+        pvData: *mut c_void,
+        cbData: usize,
+    }
+
+    impl From<AccentPolicy> for ACCENT_POLICY {
+        fn from(policy: AccentPolicy) -> Self {
+            Self {
+                AccentState: policy.state.to_raw(),
+                AccentFlags: policy.flags.bits(),
+                GradientColor: policy.gradient_color,
+                AnimationId: policy.animation_id,
+            }
+        }
+    }
+
+    unsafe fn SetWindowCompositionAttribute(
+        hwnd: HWND,
+        accent_state: AccentState,
+        options: EffectOptions,
+    ) -> Result<(), Error> {
+        let color = sanitize_color(accent_state, options.color.unwrap_or_default());
+        let is_acrylic = accent_state == AccentState::EnableAcrylicBlurBehind;
+
+        set_accent_policy(
+            hwnd,
+            AccentPolicy {
+                state: accent_state,
+                flags: options.flags.unwrap_or(if is_acrylic {
+                    AccentFlags::NONE
+                } else {
+                    AccentFlags::GRADIENT_COLOR
+                }),
+                gradient_color: color_to_gradient(color),
+                animation_id: options.animate as _,
+            },
+        )
+    }
+
+    pub fn set_accent_policy(hwnd: HWND, policy: AccentPolicy) -> Result<(), Error> {
+        unsafe { set_raw_accent_policy(hwnd, policy.into()) }
+    }
+
+    const WCA_ACCENT_POLICY: WINDOWCOMPOSITIONATTRIB = 0x13;
+
+    type WindowCompositionAttribute =
+        unsafe extern "system" fn(HWND, *mut WINDOWCOMPOSITIONATTRIBDATA) -> BOOL;
+
+    unsafe fn set_raw_accent_policy(hwnd: HWND, mut policy: ACCENT_POLICY) -> Result<(), Error> {
+        type SetWindowCompositionAttribute = WindowCompositionAttribute;
+
+        static SET_WINDOW_COMPOSITION_ATTRIBUTE: OnceCell<Option<WindowCompositionAttribute>> =
+            OnceCell::new();
+
+        if let Some(set_window_composition_attribute) =
+            cached_function(&SET_WINDOW_COMPOSITION_ATTRIBUTE, || unsafe {
+                get_function!("user32.dll", SetWindowCompositionAttribute)
+            })
+        {
+            let mut data = WINDOWCOMPOSITIONATTRIBDATA {
+                Attrib: WCA_ACCENT_POLICY,
+                pvData: &mut policy as *mut _ as _,
+                cbData: std::mem::size_of_val(&policy),
+            };
+
+            if set_window_composition_attribute(hwnd, &mut data as *mut _ as _) == 0 {
+                return Err(Error::PlatformError {
+                    call: "SetWindowCompositionAttribute",
+                    code: GetLastError() as _,
+                });
+            }
+            Ok(())
+        } else {
+            Err(Error::UnsupportedPlatformVersion(
+                "\"SetWindowCompositionAttribute()\" is not available on this system.",
+            ))
+        }
+    }
+
+    unsafe fn GetWindowCompositionAttribute(hwnd: HWND) -> Result<ACCENT_POLICY, Error> {
+        type GetWindowCompositionAttribute = WindowCompositionAttribute;
+
+        static GET_WINDOW_COMPOSITION_ATTRIBUTE: OnceCell<Option<WindowCompositionAttribute>> =
+            OnceCell::new();
+
+        if let Some(get_window_composition_attribute) =
+            cached_function(&GET_WINDOW_COMPOSITION_ATTRIBUTE, || unsafe {
+                get_function!("user32.dll", GetWindowCompositionAttribute)
+            })
+        {
+            let mut policy = ACCENT_POLICY {
+                AccentState: 0,
+                AccentFlags: 0,
+                GradientColor: 0,
+                AnimationId: 0,
+            };
+            let mut data = WINDOWCOMPOSITIONATTRIBDATA {
+                Attrib: WCA_ACCENT_POLICY,
+                pvData: &mut policy as *mut _ as _,
+                cbData: std::mem::size_of_val(&policy),
+            };
+
+            if get_window_composition_attribute(hwnd, &mut data as *mut _ as _) == 0 {
+                return Err(Error::PlatformError {
+                    call: "GetWindowCompositionAttribute",
+                    code: GetLastError() as _,
+                });
+            }
+            Ok(policy)
+        } else {
+            Err(Error::UnsupportedPlatformVersion(
+                "\"GetWindowCompositionAttribute()\" is not available on this system.",
+            ))
+        }
+    }
+
+    /// Substitutes the minimal non-zero alpha for a fully transparent tint color of blur and acrylic,
+    /// DWM treats a zero gradient color specially and draws a black or fully transparent window instead.
+    fn sanitize_color(accent_state: AccentState, mut color: Color) -> Color {
+        let is_blur = matches!(
+            accent_state,
+            AccentState::EnableBlurBehind | AccentState::EnableAcrylicBlurBehind
+        );
+        if is_blur && color.3 == 0 {
+            color.3 = 1;
+        }
+        color
+    }
+
+    const DWMWA_MICA_EFFECT: DWMWINDOWATTRIBUTE = 1029i32;
+    const DWMWA_USE_IMMERSIVE_DARK_MODE_BEFORE_20H1: DWMWINDOWATTRIBUTE = 19i32;
+    const DWMWA_SYSTEMBACKDROP_TYPE: DWMWINDOWATTRIBUTE = 38i32;
+
+    #[repr(C)]
+    enum DWM_SYSTEMBACKDROP_TYPE {
+        DWMSBT_AUTO = 0,            // Auto
+        DWMSBT_DISABLE = 1,         // None
+        DWMSBT_MAINWINDOW = 2,      // Mica
+        DWMSBT_TRANSIENTWINDOW = 3, // Acrylic
+        DWMSBT_TABBEDWINDOW = 4,    // Tabbed
+    }
+
+    pub fn supports_blur() -> bool {
+        (is_win7() && is_composition_enabled()) || is_win8() || is_win10_swca() || is_win11()
+    }
+
+    pub fn supports_transparent() -> bool {
+        is_win10_swca() || is_win11()
+    }
+
+    /// `ACCENT_ENABLE_ACRYLICBLURBEHIND` was added in Windows 10 v1709.
+    pub fn supports_acrylic() -> bool {
+        is_at_least_build(16299)
+    }
+
+    pub fn supports_host_backdrop_accent() -> bool {
+        is_at_least_build(18362)
+    }
+
+    /// `DWMWA_USE_HOSTBACKDROPBRUSH` was added in Windows 10 v2004.
+    fn supports_host_backdrop_brush() -> bool {
+        is_at_least_build(19041)
+    }
+
+    /// `DWMWA_CLOAK` was added in Windows 8.
+    fn supports_cloak() -> bool {
+        is_win8() || is_at_least_build(10240)
+    }
+
+    pub fn supports_mica() -> bool {
+        is_win11()
+    }
+
+    pub fn supports_tabbed() -> bool {
+        is_win11_dwmsbt()
+    }
+
+    /// Desktop composition can be turned off on Windows 7 with the basic and classic themes,
+    /// it is always enabled on Windows 8 and newer.
+    fn is_composition_enabled() -> bool {
+        let mut enabled = BOOL::default();
+        unsafe { DwmIsCompositionEnabled(&mut enabled) == S_OK && enabled != 0 }
+    }
+
+    fn is_win7() -> bool {
+        let v = get_windows_ver().unwrap_or_default();
+        v.0 == 6 && v.1 == 1
+    }
+
+    /// Windows 8 and 8.1, which support blur through the accent policy.
+    fn is_win8() -> bool {
+        let v = get_windows_ver().unwrap_or_default();
+        v.0 == 6 && (v.1 == 2 || v.1 == 3)
+    }
+
+    fn is_win10_swca() -> bool {
+        let v = get_windows_ver().unwrap_or_default();
+        v.2 >= 17763 && v.2 < 22000
+    }
+
+    fn is_win11() -> bool {
+        is_at_least_build(22000)
+    }
+
+    /// `DWMWA_SYSTEMBACKDROP_TYPE` appeared in insider build 22523, but is only relied on
+    /// from the first release that shipped it, so insider builds before 22H2 use the older paths.
+    fn is_win11_dwmsbt() -> bool {
+        is_at_least_build(22621)
+    }
+
+    fn is_at_least_build(build: u32) -> bool {
+        let v = get_windows_ver().unwrap_or_default();
+        v.2 >= build
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn color_to_colorref_drops_alpha() {
+            assert_eq!(color_to_colorref((255, 0, 0, 128)), 0x0000_00FF);
+            assert_eq!(color_to_colorref((0x12, 0x34, 0x56, 0xFF)), 0x0056_3412);
+            assert_eq!(color_to_colorref((255, 255, 255, 0)), 0x00FF_FFFF);
+        }
+
+        #[test]
+        fn sanitize_color_raises_zero_alpha_of_blur_and_acrylic() {
+            assert_eq!(
+                sanitize_color(AccentState::EnableBlurBehind, (18, 18, 18, 0)),
+                (18, 18, 18, 1)
+            );
+            assert_eq!(
+                sanitize_color(AccentState::EnableAcrylicBlurBehind, (0, 0, 0, 0)),
+                (0, 0, 0, 1)
+            );
+        }
+
+        #[test]
+        fn sanitize_color_keeps_other_colors() {
+            assert_eq!(
+                sanitize_color(AccentState::EnableBlurBehind, (18, 18, 18, 125)),
+                (18, 18, 18, 125)
+            );
+            assert_eq!(
+                sanitize_color(AccentState::EnableAcrylicBlurBehind, (1, 2, 3, 1)),
+                (1, 2, 3, 1)
+            );
+            assert_eq!(
+                sanitize_color(AccentState::EnableTransparentGradient, (18, 18, 18, 0)),
+                (18, 18, 18, 0)
+            );
+            assert_eq!(
+                sanitize_color(AccentState::Disabled, (0, 0, 0, 0)),
+                (0, 0, 0, 0)
+            );
+        }
+
+        /// Serializes the tests overriding the build number, which is global to the process.
+        static BUILD_NUMBER_LOCK: Lazy<std::sync::Mutex<()>> = Lazy::new(Default::default);
+
+        /// Runs `f` with the build number overridden to `build`.
+        fn with_build<T>(build: u32, f: impl FnOnce() -> T) -> T {
+            let _guard = BUILD_NUMBER_LOCK
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner);
+            override_build_number(Some(build));
+            let result = f();
+            override_build_number(None);
+            result
+        }
+
+        #[test]
+        fn version_from_build_maps_releases() {
+            assert_eq!(version_from_build(6002), (6, 0, 6002));
+            assert_eq!(version_from_build(7599), (6, 0, 7599));
+            assert_eq!(version_from_build(7600), (6, 1, 7600));
+            assert_eq!(version_from_build(7601), (6, 1, 7601));
+            assert_eq!(version_from_build(9199), (6, 1, 9199));
+            assert_eq!(version_from_build(9200), (6, 2, 9200));
+            assert_eq!(version_from_build(9599), (6, 2, 9599));
+            assert_eq!(version_from_build(9600), (6, 3, 9600));
+            assert_eq!(version_from_build(10239), (6, 3, 10239));
+            assert_eq!(version_from_build(10240), (10, 0, 10240));
+            assert_eq!(version_from_build(22621), (10, 0, 22621));
+        }
+
+        #[test]
+        fn override_replaces_detected_build() {
+            with_build(17763, || {
+                assert_eq!(build_number(), Some(17763));
+                assert_eq!(get_windows_ver(), Some((10, 0, 17763)));
+            });
+        }
+
+        #[test]
+        fn windows_7_and_8_boundaries() {
+            assert!(!with_build(7599, is_win7));
+            assert!(with_build(7600, is_win7));
+            assert!(with_build(9199, is_win7));
+            assert!(!with_build(9200, is_win7));
+            assert!(!with_build(9199, is_win8));
+            assert!(with_build(9200, is_win8));
+            assert!(with_build(9600, is_win8));
+            assert!(!with_build(10240, is_win8));
+        }
+
+        #[test]
+        fn accent_policy_boundaries() {
+            assert!(!with_build(17762, is_win10_swca));
+            assert!(with_build(17763, is_win10_swca));
+            assert!(with_build(21999, is_win10_swca));
+            assert!(!with_build(22000, is_win10_swca));
+            assert!(!with_build(16298, supports_acrylic));
+            assert!(with_build(16299, supports_acrylic));
+            assert!(!with_build(18361, supports_host_backdrop_accent));
+            assert!(with_build(18362, supports_host_backdrop_accent));
+        }
+
+        #[test]
+        fn host_backdrop_brush_boundaries() {
+            assert!(!with_build(19040, supports_host_backdrop_brush));
+            assert!(with_build(19041, supports_host_backdrop_brush));
+            assert!(with_build(22000, supports_host_backdrop_brush));
+            // the accent policy of the host backdrop came a release earlier
+            assert!(with_build(18362, supports_host_backdrop_accent));
+            assert!(!with_build(18362, supports_host_backdrop_brush));
+        }
+
+        #[test]
+        fn windows_11_boundaries() {
+            assert!(!with_build(21999, is_win11));
+            assert!(with_build(22000, is_win11));
+            assert!(!with_build(21999, supports_mica));
+            assert!(with_build(22000, supports_mica));
+            assert!(!with_build(22620, is_win11_dwmsbt));
+            assert!(with_build(22621, is_win11_dwmsbt));
+            assert!(!with_build(22620, supports_tabbed));
+            assert!(with_build(22621, supports_tabbed));
+        }
+
+        #[test]
+        fn blur_boundaries() {
+            assert!(!with_build(6002, supports_blur));
+            // Windows 7 also needs desktop composition, which the override doesn't control
+            assert!(with_build(9200, supports_blur));
+            // Windows 10 before v1809 has no working accent policy blur
+            assert!(!with_build(10240, supports_blur));
+            assert!(!with_build(17762, supports_blur));
+            assert!(with_build(17763, supports_blur));
+            assert!(with_build(22000, supports_blur));
+            assert!(!with_build(17762, supports_transparent));
+            assert!(with_build(17763, supports_transparent));
+            assert!(with_build(22621, supports_transparent));
+        }
+
+        #[test]
+        fn scale_for_dpi_between_96_and_144() {
+            assert_eq!(scale_for_dpi(100, 96, 144), 150);
+            assert_eq!(scale_for_dpi(150, 144, 96), 100);
+            assert_eq!(scale_for_dpi(0, 96, 144), 0);
+            assert_eq!(scale_for_dpi(-10, 96, 144), -15);
+        }
+
+        #[test]
+        fn scale_for_dpi_rounds_odd_sizes() {
+            // 16.5 and -16.5 round away from zero
+            assert_eq!(scale_for_dpi(11, 96, 144), 17);
+            assert_eq!(scale_for_dpi(-11, 96, 144), -17);
+            // 11.33 and 10.67
+            assert_eq!(scale_for_dpi(17, 144, 96), 11);
+            assert_eq!(scale_for_dpi(16, 144, 96), 11);
+            // 125% scaling, 1.25 and 3.75
+            assert_eq!(scale_for_dpi(1, 96, 120), 1);
+            assert_eq!(scale_for_dpi(3, 96, 120), 4);
+        }
+
+        #[test]
+        fn scale_for_dpi_keeps_value_for_same_or_unknown_dpi() {
+            assert_eq!(scale_for_dpi(301, 144, 144), 301);
+            assert_eq!(scale_for_dpi(301, 0, 144), 301);
+        }
+
+        #[test]
+        fn region_round_trips_without_drift() {
+            let region = [(10, 20, 301, 151), (0, 0, 1, 1), (-7, 33, 99, 65)];
+            let scaled = scale_rects(&region, 96, 144);
+            assert_eq!(scale_rects(&scaled, 144, 96), region);
+
+            // the region is kept at the DPI it was given for and scaled from there on every change,
+            // so moving between monitors any number of times lands on the same pixels
+            for &dpi in &[144, 120, 192, 96, 144, 96] {
+                let scaled = scale_rects(&region, 96, dpi);
+                assert_eq!(scale_rects(&scaled, dpi, 96), region);
+            }
+        }
+
+        #[test]
+        fn stored_region_scales_from_its_dpi() {
+            let mut state = WindowState::default();
+            state.record(Some(Effect::Blur), EffectOptions::default());
+            state.blur_region = vec![(10, 20, 100, 50), (0, 0, 1, 1)];
+            state.blur_region_dpi = 96;
+
+            assert_eq!(
+                scale_rects(&state.blur_region, state.blur_region_dpi, 144),
+                vec![(15, 30, 150, 75), (0, 0, 2, 2)]
+            );
+            assert_eq!(
+                scale_rects(&state.blur_region, state.blur_region_dpi, 96),
+                state.blur_region
+            );
+            // the stored region is never replaced by a scaled one
+            assert_eq!(state.blur_region, vec![(10, 20, 100, 50), (0, 0, 1, 1)]);
+        }
+
+        #[test]
+        fn dark_mode_follows_apps_use_light_theme() {
+            assert!(is_dark(Some(0)));
+            assert!(!is_dark(Some(1)));
+            // any other value is treated as light, like Windows does
+            assert!(!is_dark(Some(2)));
+            assert!(!is_dark(None));
+        }
+
+        #[test]
+        fn accent_color_is_decoded_from_abgr() {
+            assert_eq!(color_from_abgr(0x8000_00FF), (255, 0, 0, 128));
+            assert_eq!(color_from_abgr(0x80FF_0000), (0, 0, 255, 128));
+            assert_eq!(color_from_abgr(0x7856_3412), (0x12, 0x34, 0x56, 0x78));
+            // the inverse of the gradient color of the accent policy
+            assert_eq!(color_to_gradient(color_from_abgr(0x7856_3412)), 0x7856_3412);
+        }
+
+        #[test]
+        fn colorization_color_is_decoded_from_argb() {
+            assert_eq!(color_from_argb(0x80FF_0000), (255, 0, 0, 128));
+            assert_eq!(color_from_argb(0x8000_00FF), (0, 0, 255, 128));
+            assert_eq!(color_from_argb(0x7812_3456), (0x12, 0x34, 0x56, 0x78));
+            assert_eq!(color_from_argb(0), (0, 0, 0, 0));
+            assert_eq!(color_from_argb(0xFFFF_FFFF), (255, 255, 255, 255));
+        }
+
+        #[test]
+        fn functions_are_resolved_once() {
+            unsafe extern "system" fn set_window_composition_attribute(
+                _: HWND,
+                _: *mut WINDOWCOMPOSITIONATTRIBDATA,
+            ) -> BOOL {
+                1
+            }
+
+            let loads = std::cell::Cell::new(0);
+            let cell = OnceCell::<Option<WindowCompositionAttribute>>::new();
+            for _ in 0..3 {
+                let function = cached_function(&cell, || {
+                    loads.set(loads.get() + 1);
+                    Some(set_window_composition_attribute as WindowCompositionAttribute)
+                });
+                assert!(function.is_some());
+            }
+            assert_eq!(loads.get(), 1);
+        }
+
+        #[test]
+        fn missing_functions_are_resolved_once() {
+            let loads = std::cell::Cell::new(0);
+            let cell = OnceCell::<Option<WindowCompositionAttribute>>::new();
+            for _ in 0..3 {
+                let function = cached_function(&cell, || {
+                    loads.set(loads.get() + 1);
+                    None
+                });
+                assert!(function.is_none());
+            }
+            assert_eq!(loads.get(), 1);
+        }
+
+        #[test]
+        fn switching_effects_clears_the_previous_one() {
+            let mut state = WindowState::default();
+            let suspend = EffectOptions {
+                suspend_when_maximized: true,
+                ..Default::default()
+            };
+
+            // mica has no options, so nothing needs the subclass
+            assert_eq!(effect_to_clear(state.effect, Effect::Mica), None);
+            state.record(Some(Effect::Mica), EffectOptions::default());
+            state.snapshot = Some(Snapshot {
+                accent_policy: None,
+                system_backdrop: Some(0),
+            });
+            assert_eq!(state.effect, Some(Effect::Mica));
+            assert!(!state.needs_subclass());
+
+            // mica to blur clears mica, and the snapshot with it
+            assert_eq!(
+                effect_to_clear(state.effect, Effect::Blur),
+                Some(Effect::Mica)
+            );
+            assert!(!state.forget(Effect::Blur));
+            assert_eq!(state.effect, Some(Effect::Mica));
+            assert!(state.forget(Effect::Mica));
+            assert_eq!(state.effect, None);
+            assert!(state.snapshot.is_none());
+            state.record(Some(Effect::Blur), suspend);
+            state.blur_region = vec![(0, 0, 10, 10)];
+            state.suspended = true;
+            assert!(state.needs_subclass());
+
+            // applying blur again keeps it
+            assert_eq!(effect_to_clear(state.effect, Effect::Blur), None);
+
+            // blur to acrylic drops what was left of blur
+            assert_eq!(
+                effect_to_clear(state.effect, Effect::Acrylic),
+                Some(Effect::Blur)
+            );
+            assert!(state.forget(Effect::Blur));
+            state.record(Some(Effect::Acrylic), EffectOptions::default());
+            assert_eq!(state.effect, Some(Effect::Acrylic));
+            assert_eq!(state.options, EffectOptions::default());
+            assert!(state.blur_region.is_empty());
+            assert!(!state.suspended);
+            assert!(!state.needs_subclass());
+        }
+
+        #[test]
+        fn recording_an_effect_keeps_the_snapshot() {
+            let mut state = WindowState {
+                snapshot: Some(Snapshot {
+                    accent_policy: None,
+                    system_backdrop: Some(2),
+                }),
+                disabled: true,
+                ..Default::default()
+            };
+            state.record(Some(Effect::Acrylic), EffectOptions::default());
+            assert!(state.snapshot.is_some());
+            assert!(!state.disabled);
+            state.record(None, EffectOptions::default());
+            assert!(state.snapshot.is_none());
+            assert!(state.is_empty());
+        }
+
+        #[test]
+        fn child_style_is_only_ws_child() {
+            use windows_sys::Win32::UI::WindowsAndMessaging::{
+                WS_CLIPCHILDREN, WS_OVERLAPPEDWINDOW, WS_POPUP, WS_VISIBLE,
+            };
+
+            assert!(is_child_style(WS_CHILD));
+            assert!(is_child_style(WS_CHILD | WS_VISIBLE | WS_CLIPCHILDREN));
+            assert!(!is_child_style(0));
+            assert!(!is_child_style(WS_OVERLAPPEDWINDOW | WS_VISIBLE));
+            // popups like menus and tooltips are top-level windows
+            assert!(!is_child_style(WS_POPUP | WS_VISIBLE));
+        }
+
+        #[test]
+        fn nc_rendering_policy_values() {
+            assert_eq!(nc_rendering_policy(true), DWMNCRP_USEWINDOWSTYLE);
+            assert_eq!(nc_rendering_policy(false), DWMNCRP_DISABLED);
+        }
+
+        #[test]
+        fn frame_is_extended_again_after_enabling_nc_rendering() {
+            let margins = Margins::Explicit {
+                left: 0,
+                right: 0,
+                top: 32,
+                bottom: 0,
+            };
+            // nothing to extend while the rendering is disabled, or without margins given before
+            assert_eq!(frame_to_extend_again(false, Some((margins, 96)), 96), None);
+            assert_eq!(frame_to_extend_again(true, None, 96), None);
+
+            assert_eq!(
+                frame_to_extend_again(true, Some((margins, 96)), 96),
+                Some(margins)
+            );
+            // the window may have moved to another monitor while the rendering was disabled
+            assert_eq!(
+                frame_to_extend_again(true, Some((margins, 96)), 144),
+                Some(Margins::Explicit {
+                    left: 0,
+                    right: 0,
+                    top: 48,
+                    bottom: 0,
+                })
+            );
+        }
+
+        #[test]
+        fn sheet_margins_are_not_scaled() {
+            assert_eq!(scale_margins(Margins::Sheet, 96, 144), Margins::Sheet);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accent_flags_have_the_documented_bits() {
+        assert_eq!(AccentFlags::NONE.bits(), 0);
+        assert_eq!(AccentFlags::GRADIENT_COLOR.bits(), 0x2);
+        assert_eq!(AccentFlags::DRAW_LEFT_BORDER.bits(), 0x20);
+        assert_eq!(AccentFlags::DRAW_TOP_BORDER.bits(), 0x40);
+        assert_eq!(AccentFlags::DRAW_RIGHT_BORDER.bits(), 0x80);
+        assert_eq!(AccentFlags::DRAW_BOTTOM_BORDER.bits(), 0x100);
+        assert_eq!(AccentFlags::default(), AccentFlags::NONE);
+    }
+
+    #[test]
+    fn accent_flags_combine_with_or() {
+        let all = AccentFlags::DRAW_LEFT_BORDER
+            | AccentFlags::DRAW_TOP_BORDER
+            | AccentFlags::DRAW_RIGHT_BORDER
+            | AccentFlags::DRAW_BOTTOM_BORDER;
+        assert_eq!(all, AccentFlags::DRAW_ALL_BORDERS);
+        assert!(!all.contains(AccentFlags::GRADIENT_COLOR));
+
+        let mut flags = AccentFlags::GRADIENT_COLOR;
+        flags |= AccentFlags::DRAW_TOP_BORDER;
+        assert_eq!(flags.bits(), 0x42);
+        assert!(flags.contains(AccentFlags::GRADIENT_COLOR));
+        assert!(flags.contains(AccentFlags::DRAW_TOP_BORDER));
+        assert!(!flags.contains(AccentFlags::DRAW_ALL_BORDERS));
+        assert!(flags.contains(AccentFlags::NONE));
+        assert_eq!(flags | AccentFlags::NONE, flags);
+    }
+
+    #[test]
+    fn color_to_gradient_swaps_red_and_blue() {
+        assert_eq!(color_to_gradient((255, 0, 0, 128)), 0x8000_00FF);
+        assert_eq!(color_to_gradient((0, 0, 255, 128)), 0x80FF_0000);
+        assert_eq!(color_to_gradient((0x12, 0x34, 0x56, 0x78)), 0x7856_3412);
+    }
+
+    #[test]
+    fn color_to_gradient_keeps_extremes() {
+        assert_eq!(color_to_gradient((0, 0, 0, 0)), 0);
+        assert_eq!(color_to_gradient((255, 255, 255, 255)), 0xFFFF_FFFF);
+    }
+
+    #[test]
+    fn accent_policy_uses_gradient_color() {
+        let policy =
+            AccentPolicy::new(AccentState::EnableBlurBehind).with_gradient_color((255, 0, 0, 128));
+        assert_eq!(policy.gradient_color, 0x8000_00FF);
+    }
 }
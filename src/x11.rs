@@ -0,0 +1,227 @@
+// Copyright 2019-2022 Tauri Programme within The Commons Conservancy
+// SPDX-License-Identifier: Apache-2.0
+// SPDX-License-Identifier: MIT
+
+// KWin blurs what is behind the parts of a window listed in `_KDE_NET_WM_BLUR_BEHIND_REGION`,
+// see https://invent.kde.org/plasma/kwin/-/blob/master/src/plugins/blur/blur.cpp
+
+use crate::Error;
+use once_cell::sync::Lazy;
+use raw_window_handle::RawDisplayHandle;
+use std::{
+    ffi::{c_void, CString},
+    os::raw::{c_int, c_long, c_uchar, c_ulong},
+    ptr,
+    sync::{
+        atomic::{AtomicU16, Ordering},
+        Mutex, PoisonError,
+    },
+};
+use x11_dl::xlib::{self, Display, XErrorEvent, Xlib};
+
+const BLUR_BEHIND_REGION: &str = "_KDE_NET_WM_BLUR_BEHIND_REGION";
+
+// major opcodes of the core protocol requests made here, x11-dl doesn't define them
+const X_INTERN_ATOM: u8 = 16;
+const X_CHANGE_PROPERTY: u8 = 18;
+const X_DELETE_PROPERTY: u8 = 19;
+const X_GET_PROPERTY: u8 = 20;
+
+pub fn apply_blur(
+    display: *mut c_void,
+    window: c_ulong,
+    rects: &[(i32, i32, i32, i32)],
+) -> Result<(), Error> {
+    // each rectangle is stored as four cardinals, an empty list blurs the whole window
+    let mut region: Vec<c_long> = Vec::with_capacity(rects.len() * 4);
+    for &(x, y, width, height) in rects {
+        if x < 0 || y < 0 || width < 0 || height < 0 {
+            return Err(Error::InvalidArgument(
+                "Blur on X11 requires the rectangles to have no negative coordinates or sizes.",
+            ));
+        }
+        region.extend_from_slice(&[x as c_long, y as c_long, width as c_long, height as c_long]);
+    }
+
+    with_display(display, |xlib, display| unsafe {
+        // whether the atom exists says nothing about KWin, any client may have interned it and KWin
+        // may not have yet, so the property is always set and compositors without blur ignore it
+        let atom = blur_atom(xlib, display, false);
+        // format 32 properties are passed as longs, whatever their size on the server
+        (xlib.XChangeProperty)(
+            display,
+            window,
+            atom,
+            xlib::XA_CARDINAL,
+            32,
+            xlib::PropModeReplace,
+            region.as_ptr() as *const c_uchar,
+            region.len() as c_int,
+        );
+        Ok(())
+    })
+}
+
+pub fn clear_blur(display: *mut c_void, window: c_ulong) -> Result<bool, Error> {
+    with_display(display, |xlib, display| unsafe {
+        let atom = blur_atom(xlib, display, true);
+        if atom == 0 {
+            // no window on this display can have the property yet
+            return Ok(false);
+        }
+
+        let mut actual_type: c_ulong = 0;
+        let mut actual_format: c_int = 0;
+        let mut count: c_ulong = 0;
+        let mut remaining: c_ulong = 0;
+        let mut data: *mut c_uchar = ptr::null_mut();
+        (xlib.XGetWindowProperty)(
+            display,
+            window,
+            atom,
+            0,
+            0,
+            xlib::False,
+            xlib::AnyPropertyType as c_ulong,
+            &mut actual_type,
+            &mut actual_format,
+            &mut count,
+            &mut remaining,
+            &mut data,
+        );
+        if !data.is_null() {
+            (xlib.XFree)(data as _);
+        }
+        if actual_type == 0 {
+            return Ok(false);
+        }
+        (xlib.XDeleteProperty)(display, window, atom);
+        Ok(true)
+    })
+}
+
+/// Returns the Xlib display of `handle`, or null for other display handles.
+pub fn xlib_display(handle: RawDisplayHandle) -> *mut c_void {
+    match handle {
+        RawDisplayHandle::Xlib(handle) => handle.display,
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Returns the blur atom, creating it unless `only_if_exists`, or `0` if it doesn't exist then.
+unsafe fn blur_atom(xlib: &Xlib, display: *mut Display, only_if_exists: bool) -> c_ulong {
+    let name = CString::new(BLUR_BEHIND_REGION).expect("the atom name has no NUL");
+    let only_if_exists = if only_if_exists {
+        xlib::True
+    } else {
+        xlib::False
+    };
+    (xlib.XInternAtom)(display, name.as_ptr(), only_if_exists)
+}
+
+/// libX11 is loaded once, like `SetWindowCompositionAttribute` on Windows.
+static XLIB: Lazy<Option<Xlib>> = Lazy::new(|| Xlib::open().ok());
+
+/// The error handler of Xlib is global, so only one call may replace it at a time.
+static ERROR_HANDLER_LOCK: Lazy<Mutex<()>> = Lazy::new(Default::default);
+
+/// The first error reported to `record_error()`, as `request_code << 8 | error_code`, or `0`.
+static ERROR: AtomicU16 = AtomicU16::new(0);
+
+unsafe extern "C" fn record_error(_display: *mut Display, event: *mut XErrorEvent) -> c_int {
+    let error = (u16::from((*event).request_code) << 8) | u16::from((*event).error_code);
+    let _ = ERROR.compare_exchange(0, error, Ordering::SeqCst, Ordering::SeqCst);
+    0
+}
+
+/// Returns the name of the Xlib call that sent the request with the major opcode `request_code`.
+fn request_name(request_code: u8) -> &'static str {
+    match request_code {
+        X_INTERN_ATOM => "XInternAtom",
+        X_CHANGE_PROPERTY => "XChangeProperty",
+        X_DELETE_PROPERTY => "XDeleteProperty",
+        X_GET_PROPERTY => "XGetWindowProperty",
+        _ => "XSync",
+    }
+}
+
+/// Returns the error for a value recorded by `record_error()`.
+fn platform_error(error: u16) -> Error {
+    Error::PlatformError {
+        call: request_name((error >> 8) as u8),
+        code: i32::from(error as u8),
+    }
+}
+
+/// Calls `f` with the Xlib `display` of the window, or with a new connection to the display of `DISPLAY` if it is null.
+///
+/// Window ids are the same for every client of a display, so a new connection works as long as the window
+/// is on the display of `DISPLAY`. Properties stay on the window after the connection is closed.
+///
+/// The default error handler of Xlib exits the process, like for a window that was destroyed already, so
+/// the requests of `f` are made with a handler that turns the first error into [`Error::PlatformError`].
+fn with_display<T>(
+    display: *mut c_void,
+    f: impl FnOnce(&Xlib, *mut Display) -> Result<T, Error>,
+) -> Result<T, Error> {
+    let xlib = XLIB.as_ref().ok_or(Error::UnsupportedPlatform(
+        "Blur on X11 requires libX11, which couldn't be loaded.",
+    ))?;
+    unsafe {
+        let opened = display.is_null();
+        let display = if opened {
+            (xlib.XOpenDisplay)(ptr::null())
+        } else {
+            display as *mut Display
+        };
+        if display.is_null() {
+            return Err(Error::UnsupportedPlatform(
+                "Blur on X11 requires a connection to the X server, which couldn't be opened.",
+            ));
+        }
+
+        let result = {
+            let _lock = ERROR_HANDLER_LOCK
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            // errors of requests sent before belong to the previous handler
+            (xlib.XSync)(display, xlib::False);
+            ERROR.store(0, Ordering::SeqCst);
+            let previous = (xlib.XSetErrorHandler)(Some(record_error));
+            let result = f(xlib, display);
+            // waits for the replies, so every error of the requests arrived before the handler is restored
+            (xlib.XSync)(display, xlib::False);
+            (xlib.XSetErrorHandler)(previous);
+            match ERROR.load(Ordering::SeqCst) {
+                0 => result,
+                error => Err(platform_error(error)),
+            }
+        };
+
+        if opened {
+            (xlib.XCloseDisplay)(display);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn platform_error_names_the_request() {
+        let error = (u16::from(X_CHANGE_PROPERTY) << 8) | u16::from(xlib::BadWindow);
+        match platform_error(error) {
+            Error::PlatformError { call, code } => {
+                assert_eq!(call, "XChangeProperty");
+                assert_eq!(code, i32::from(xlib::BadWindow));
+            }
+            error => panic!("unexpected error {:?}", error),
+        }
+        assert_eq!(request_name(X_GET_PROPERTY), "XGetWindowProperty");
+        assert_eq!(request_name(X_DELETE_PROPERTY), "XDeleteProperty");
+        assert_eq!(request_name(X_INTERN_ATOM), "XInternAtom");
+        assert_eq!(request_name(0), "XSync");
+    }
+}